@@ -1,10 +1,10 @@
 use bevy::{math::vec3, prelude::*};
 
 use crate::{
+    droplet_pool::DropletPool,
     enemy::{Enemy, SpawnWaveEvent},
-    gadget::Water,
-    game_state::WaveControler,
-    polishing_constants::ATTACK_COUNTDOWN,
+    game_state::{RunStats, Wallet, WaveControler},
+    polishing_constants::{ATTACK_COUNTDOWN, WAVE_BONUS_GOLD},
     AppState, WORLD_SIZE,
 };
 
@@ -31,15 +31,21 @@ fn attack_system(
     time: Res<Time>,
     mut timer_q: Query<(&mut AttackStateCountdown, &mut Text)>,
     mut state: ResMut<State<AppState>>,
+    mut run_stats: ResMut<RunStats>,
+    mut wallet: ResMut<Wallet>,
 ) {
     let (mut countdown, mut text) = timer_q.single_mut();
     countdown.countdown -= time.delta_seconds_f64();
     text.sections[0].value = format!("{:.3}s", countdown.countdown);
 
+    // a pause queued earlier this frame wins, the phase then ends once it's resumed
     if countdown.countdown <= 0.0 {
-        state
-            .set(AppState::Build)
-            .expect("Couldn't switch state to Attack");
+        if let Err(err) = state.set(AppState::Build) {
+            warn!("couldn't switch state to Build: {:?}", err);
+            return;
+        }
+        run_stats.waves_survived += 1;
+        wallet.balance += WAVE_BONUS_GOLD;
     }
 }
 
@@ -95,7 +101,7 @@ fn cleanup(
     mut timer_q: Query<(Entity, &mut AttackStateCountdown)>,
     mut text_q: Query<(Entity, &mut AttackStateText)>,
     mut enemy_q: Query<Entity, With<Enemy>>,
-    mut water_q: Query<Entity, With<Water>>,
+    mut droplet_pool: ResMut<DropletPool>,
 ) {
     let (entity, _) = timer_q.single_mut();
     commands.entity(entity).despawn_recursive();
@@ -108,7 +114,5 @@ fn cleanup(
         commands.entity(entity).despawn_recursive();
     }
 
-    for entity in water_q.iter() {
-        commands.entity(entity).despawn_recursive();
-    }
+    droplet_pool.release_all(&mut commands);
 }
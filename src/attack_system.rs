@@ -1,85 +1,155 @@
-use bevy::{math::vec3, prelude::*};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
 
 use crate::{
-    gadget::{shoot_water, Gadget},
-    polishing_constants::{GADGET_MIN_DISTANCE, SHOOTING_CHANCE},
+    ballistics::{solve_trajectory, Trajectory},
+    droplet_pool::DropletPool,
+    enemy::{Enemy, Health, SpawnedAt},
+    flow_field::FlowField,
+    gadget::{shoot_lava, shoot_water, CannonGadget, Gadget, DROPLET_COLLISION_GROUPS},
+    gadget_catalog::{GadgetKind, Projectile},
+    game_state::{unless_frozen, RunStats},
+    level_asset::PathfindingMode,
+    pathfinding::PathfindingAgent,
+    polishing_constants::{AIM_MAX_FLIGHT_SECS, PROJECTILE_SPEED_PER_STRENGTH},
+    spatial_index::SpatialIndex,
+    targeting::{TargetCandidate, TargetingMode},
 };
 
 pub struct AttackSystemPlugin;
 
 impl Plugin for AttackSystemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(attack_system);
+        app.add_system(attack_system.with_run_criteria(unless_frozen));
+    }
+}
+
+/// Everything a gadget needs to pick an enemy and aim at it.
+#[derive(SystemParam)]
+struct TargetFinder<'w, 's> {
+    enemies: Query<
+        'w,
+        's,
+        (
+            &'static Velocity,
+            &'static Health,
+            &'static PathfindingAgent,
+            &'static SpawnedAt,
+        ),
+        With<Enemy>,
+    >,
+    spatial_index: Res<'w, SpatialIndex>,
+    flow_field: Res<'w, FlowField>,
+    pathfinding_mode: Res<'w, PathfindingMode>,
+    rapier_config: Res<'w, RapierConfiguration>,
+    rapier_context: Res<'w, RapierContext>,
+}
+
+impl<'w, 's> TargetFinder<'w, 's> {
+    /// Flight of a shot from `origin` at the best ranked enemy in range that can
+    /// actually be hit, out of reach ones have to come closer.
+    fn aim(&self, origin: Vec2, cannon: &CannonGadget, mode: TargetingMode) -> Option<Trajectory> {
+        // only fixed geometry blocks shots, enemies and droplets in the way move on
+        let line_of_sight = QueryFilter::only_fixed()
+            .exclude_sensors()
+            .groups(DROPLET_COLLISION_GROUPS.into());
+        let speed = cannon.emission_strength * PROJECTILE_SPEED_PER_STRENGTH;
+        let aim_at = |enemy_pos: Vec2, enemy_velocity: Vec2| {
+            solve_trajectory(
+                origin,
+                enemy_pos,
+                enemy_velocity,
+                speed,
+                self.rapier_config.gravity,
+                AIM_MAX_FLIGHT_SECS,
+            )
+            .filter(|trajectory| trajectory.is_clear(&self.rapier_context, line_of_sight))
+        };
+
+        // the nearest enemy is usually in sight, which spares ranking everyone in range
+        if mode == TargetingMode::Closest {
+            let nearest =
+                self.spatial_index
+                    .nearest(origin, cannon.range)
+                    .and_then(|(entity, enemy_pos)| {
+                        let (velocity, ..) = self.enemies.get(entity).ok()?;
+                        aim_at(enemy_pos, velocity.linvel)
+                    });
+            if nearest.is_some() {
+                return nearest;
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for (entity, enemy_pos) in self.spatial_index.within_radius(origin, cannon.range) {
+            let (velocity, health, agent, spawned_at) = match self.enemies.get(entity) {
+                Ok(enemy) => enemy,
+                Err(_) => continue,
+            };
+
+            let rank = mode.rank(&TargetCandidate {
+                distance: origin.distance(enemy_pos),
+                remaining_path: agent.remaining_path_length(
+                    enemy_pos,
+                    &self.flow_field,
+                    *self.pathfinding_mode,
+                ),
+                health: health.current,
+                spawned_at: spawned_at.0,
+            });
+            candidates.push((rank, enemy_pos, velocity.linvel));
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        candidates
+            .iter()
+            .find_map(|(_, enemy_pos, enemy_velocity)| aim_at(*enemy_pos, *enemy_velocity))
     }
 }
 
 fn attack_system(
-    gadgets: Query<(&Transform, &Gadget)>,
-    enemies: Query<&Transform, With<crate::enemy::Enemy>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut gadgets: Query<(
+        &Transform,
+        &Gadget,
+        &GadgetKind,
+        &TargetingMode,
+        &mut CannonGadget,
+    )>,
+    target_finder: TargetFinder,
+    mut droplet_pool: ResMut<DropletPool>,
     mut commands: Commands,
+    mut run_stats: ResMut<RunStats>,
+    time: Res<Time>,
 ) {
-    for (transform, gadget) in gadgets.iter() {
+    for (transform, gadget, gadget_kind, targeting_mode, mut cannon) in gadgets.iter_mut() {
         if !gadget.is_placed {
             continue;
         }
 
-        //new variable random value between 0 and 1
-        let random_value = rand::random::<f32>();
-
-        if random_value < SHOOTING_CHANCE {
+        // stays finished until there is something to shoot at
+        cannon.cooldown.tick(time.delta());
+        if !cannon.cooldown.finished() {
             continue;
         }
 
-        let mut min_distance = f32::INFINITY;
-        let mut enemy_position = None;
-        for enemy in enemies.iter() {
-            let gadget_pos = transform.translation;
-            let loc_enemy_pos = enemy.translation;
-            let distance = (gadget_pos - loc_enemy_pos).length();
-
-            if distance < min_distance {
-                min_distance = distance;
-                enemy_position = Some(loc_enemy_pos);
-            }
-        }
+        let trajectory =
+            target_finder.aim(transform.translation.truncate(), &cannon, *targeting_mode);
 
-        if min_distance > GADGET_MIN_DISTANCE {
-            continue;
-        }
-
-        //now we have the nearest enemy
         // shoot wa'er
-        if let Some(pos) = enemy_position {
-            if !is_shooting_down(transform.translation.truncate(), pos.truncate()) {
-                shoot_water(
-                    transform.translation,
-                    pos,
-                    &mut meshes,
-                    &mut materials,
-                    &mut commands,
-                );
-            } 
-        }
-    }
-
-    fn is_shooting_down(cannon_pos: Vec2, enemy_pos: Vec2) -> bool {
-        let direction_vec = (enemy_pos - cannon_pos).normalize();
-
-        if direction_vec.y > 0.0 {
-            return false;
+        if let Some(trajectory) = trajectory {
+            let shoot = match gadget_kind.definition().projectile {
+                Projectile::Water => shoot_water,
+                Projectile::Lava => shoot_lava,
+            };
+            shoot(
+                transform.translation,
+                trajectory.launch_velocity,
+                cannon.projectile_size,
+                &mut droplet_pool,
+                &mut commands,
+            );
+            run_stats.shots_fired += 1;
+            cannon.cooldown.reset();
         }
-
-        let a = Vec2::X;
-        let b = direction_vec;
-
-        let angle = (a.dot(b) / (a.length() * b.length())).acos().to_degrees();
-
-        dbg!(angle);
-
-        angle > 20.0 && angle < 160.0
-
-
     }
 }
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+/// Steps used to look for the earliest flight time that hits the target.
+const FLIGHT_TIME_STEP: f32 = 1.0 / 30.0;
+/// Bisection rounds to refine a flight time once a hit is bracketed.
+const REFINE_STEPS: u32 = 12;
+/// Straight pieces the arc is split into for line of sight checks.
+const ARC_SEGMENTS: u32 = 8;
+
+/// The flight of a projectile from its launch until it meets its target.
+#[derive(Debug, Clone, Copy)]
+pub struct Trajectory {
+    pub origin: Vec2,
+    pub launch_velocity: Vec2,
+    pub gravity: Vec2,
+    pub flight_time: f32,
+}
+
+impl Trajectory {
+    /// Where the projectile is `t` seconds after the launch.
+    pub fn point_at(&self, t: f32) -> Vec2 {
+        self.origin + self.launch_velocity * t + self.gravity * t * t * 0.5
+    }
+
+    /// Whether the arc gets to the target without running into anything the
+    /// projectile would collide with according to `filter`.
+    pub fn is_clear(&self, rapier_context: &RapierContext, filter: QueryFilter) -> bool {
+        (0..ARC_SEGMENTS).all(|segment| {
+            let from = self.point_at(self.flight_time * segment as f32 / ARC_SEGMENTS as f32);
+            let to = self.point_at(self.flight_time * (segment + 1) as f32 / ARC_SEGMENTS as f32);
+            // a direction of the segment's length makes the time of impact a fraction of it
+            rapier_context
+                .cast_ray(from, to - from, 1.0, true, filter)
+                .is_none()
+        })
+    }
+}
+
+/// Flight of a projectile leaving `origin` with `speed` under `gravity` that
+/// meets a target at `target` moving with `target_velocity`.
+///
+/// Prefers the flattest arc, which is also the shortest flight. Returns `None`
+/// if the target can't be reached within `max_flight_time` seconds.
+pub fn solve_trajectory(
+    origin: Vec2,
+    target: Vec2,
+    target_velocity: Vec2,
+    speed: f32,
+    gravity: Vec2,
+    max_flight_time: f32,
+) -> Option<Trajectory> {
+    // velocity needed to be at the target's predicted position after `t` seconds
+    let launch_velocity = |t: f32| (target - origin + target_velocity * t) / t - gravity * t * 0.5;
+    // positive while the projectile would need to be faster than it is
+    let excess_speed = |t: f32| launch_velocity(t).length_squared() - speed * speed;
+    let trajectory = |flight_time: f32| Trajectory {
+        origin,
+        launch_velocity: launch_velocity(flight_time),
+        gravity,
+        flight_time,
+    };
+
+    let mut early = FLIGHT_TIME_STEP;
+    if excess_speed(early) <= 0.0 {
+        return Some(trajectory(early));
+    }
+
+    while early < max_flight_time {
+        let late = (early + FLIGHT_TIME_STEP).min(max_flight_time);
+        if excess_speed(late) <= 0.0 {
+            let mut hit = late;
+            for _ in 0..REFINE_STEPS {
+                let middle = (early + hit) * 0.5;
+                if excess_speed(middle) <= 0.0 {
+                    hit = middle;
+                } else {
+                    early = middle;
+                }
+            }
+            return Some(trajectory(hit));
+        }
+        early = late;
+    }
+
+    None
+}
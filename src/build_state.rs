@@ -30,10 +30,11 @@ fn build_system(
     countdown.countdown -= time.delta_seconds_f64();
     text.sections[0].value = format!("{:.3}s", countdown.countdown);
 
+    // a pause queued earlier this frame wins, the phase then ends once it's resumed
     if countdown.countdown <= 0.0 {
-        state
-            .set(AppState::Attack)
-            .expect("Couldn't switch state to Attack");
+        if let Err(err) = state.set(AppState::Attack) {
+            warn!("couldn't switch state to Attack: {:?}", err);
+        }
     }
 }
 
@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    enemy::{BurnOnContact, ContactDamage},
+    gadget::{Lava, Water},
+    gadget_catalog::Projectile,
+    game_state::unless_frozen,
+    level_asset::ChunkDescription,
+    polishing_constants::{DROPLET_LIFETIME_SECS, MAX_LIVE_DROPLETS},
+};
+
+/// Below this speed in pixels per second a droplet counts as lying still.
+const REST_SPEED: f32 = 20.0;
+/// How long a droplet lies still before it gets recycled, long enough for water
+/// to pool up where swimmers can use it.
+const REST_SECS: f32 = 2.0;
+/// Pooled droplets don't touch anything while they wait to be shot again.
+const INACTIVE_COLLISION_GROUPS: CollisionGroups = CollisionGroups::new(Group::NONE, Group::NONE);
+
+pub struct DropletPoolPlugin;
+
+/// Droplets with what `expire_droplets` needs to decide whether they are used up.
+type DropletQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Droplet,
+        &'static Velocity,
+        &'static Visibility,
+        Option<&'static ContactDamage>,
+        Option<&'static Water>,
+    ),
+>;
+
+/// A water or lava droplet shot by a cannon, recycled once it is used up.
+#[derive(Component)]
+pub struct Droplet {
+    lifetime: Timer,
+    resting: Timer,
+}
+
+impl Default for Droplet {
+    fn default() -> Self {
+        Droplet {
+            lifetime: Timer::from_seconds(DROPLET_LIFETIME_SECS, false),
+            resting: Timer::from_seconds(REST_SECS, false),
+        }
+    }
+}
+
+/// Droplet entities and the mesh and materials they all share.
+///
+/// Droplets are never despawned, used up ones get hidden and put to sleep
+/// without collisions until a cannon shoots them again.
+pub struct DropletPool {
+    mesh: Mesh2dHandle,
+    water: Handle<ColorMaterial>,
+    lava: Handle<ColorMaterial>,
+    /// Droplets in flight, oldest first.
+    live: VecDeque<Entity>,
+    free: Vec<Entity>,
+}
+
+impl FromWorld for DropletPool {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Circle::default().into())
+            .into();
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        let mut material = |hex: &str| materials.add(ColorMaterial::from(Color::hex(hex).unwrap()));
+
+        DropletPool {
+            mesh,
+            water: material("27636E"),
+            lava: material("E2521D"),
+            live: VecDeque::new(),
+            free: Vec::new(),
+        }
+    }
+}
+
+impl Plugin for DropletPoolPlugin {
+    fn build(&self, app: &mut App) {
+        // after the update stage, so hits and newly shot droplets are already applied
+        app.init_resource::<DropletPool>().add_system_to_stage(
+            CoreStage::PostUpdate,
+            expire_droplets.with_run_criteria(unless_frozen),
+        );
+    }
+}
+
+impl DropletPool {
+    pub fn mesh(&self) -> Mesh2dHandle {
+        self.mesh.clone()
+    }
+
+    pub fn material(&self, projectile: Projectile) -> Handle<ColorMaterial> {
+        match projectile {
+            Projectile::Water => self.water.clone(),
+            Projectile::Lava => self.lava.clone(),
+        }
+    }
+
+    /// Entity for a new droplet. Recycled droplets are used first, then new ones
+    /// are spawned up to `MAX_LIVE_DROPLETS` and after that the oldest droplet in
+    /// flight makes way.
+    pub fn acquire(&mut self, commands: &mut Commands) -> Entity {
+        let entity = match self.free.pop() {
+            Some(entity) => entity,
+            None if self.live.is_empty() || self.live.len() < MAX_LIVE_DROPLETS => {
+                commands.spawn().id()
+            }
+            None => {
+                let oldest = self.live.pop_front().unwrap();
+                Self::strip(oldest, commands);
+                oldest
+            }
+        };
+        self.live.push_back(entity);
+        entity
+    }
+
+    /// Takes a droplet out of the game until it gets shot again.
+    pub fn release(&mut self, entity: Entity, commands: &mut Commands) {
+        if let Some(idx) = self.live.iter().position(|live| *live == entity) {
+            self.live.remove(idx);
+            self.park(entity, commands);
+        }
+    }
+
+    /// Releases every droplet in flight, e.g. when an attack phase ends.
+    pub fn release_all(&mut self, commands: &mut Commands) {
+        while let Some(entity) = self.live.pop_front() {
+            self.park(entity, commands);
+        }
+    }
+
+    fn park(&mut self, entity: Entity, commands: &mut Commands) {
+        Self::strip(entity, commands);
+        // stays a dynamic body, so queries for fixed level geometry don't see it,
+        // and touching gravity or velocity here would wake it up again
+        commands
+            .entity(entity)
+            .insert(Sleeping {
+                sleeping: true,
+                ..default()
+            })
+            .insert(INACTIVE_COLLISION_GROUPS)
+            .insert(Visibility { is_visible: false });
+        self.free.push(entity);
+    }
+
+    /// Removes what made the droplet water or lava.
+    fn strip(entity: Entity, commands: &mut Commands) {
+        commands
+            .entity(entity)
+            .remove::<ContactDamage>()
+            .remove::<BurnOnContact>()
+            .remove::<Water>()
+            .remove::<Lava>();
+    }
+}
+
+/// Recycles droplets that hit an enemy, came to rest or are too old.
+///
+/// Lava splashes when it hits level geometry, water settles into pools for
+/// swimmers and only goes once it has rested for a while.
+fn expire_droplets(
+    mut commands: Commands,
+    mut droplet_pool: ResMut<DropletPool>,
+    time: Res<Time>,
+    mut collision_events: EventReader<CollisionEvent>,
+    geometry_query: Query<(), With<ChunkDescription>>,
+    mut droplet_query: DropletQuery,
+) {
+    let mut hit_geometry = Vec::new();
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _) = collision_event {
+            for (droplet, other) in [(*a, *b), (*b, *a)] {
+                if geometry_query.contains(other) {
+                    hit_geometry.push(droplet);
+                }
+            }
+        }
+    }
+
+    for (entity, mut droplet, velocity, visibility, contact_damage, water) in
+        droplet_query.iter_mut()
+    {
+        if !visibility.is_visible {
+            continue;
+        }
+
+        droplet.lifetime.tick(time.delta());
+        if velocity.linvel.length() < REST_SPEED {
+            droplet.resting.tick(time.delta());
+        } else {
+            droplet.resting.reset();
+        }
+
+        // hitting an enemy uses up the droplet's contact damage
+        let hit_enemy = contact_damage.is_none();
+        let splashed = water.is_none() && hit_geometry.contains(&entity);
+        if hit_enemy || splashed || droplet.lifetime.finished() || droplet.resting.finished() {
+            droplet_pool.release(entity, &mut commands);
+        }
+    }
+}
@@ -7,46 +7,81 @@ use bevy_rapier2d::prelude::*;
 pub struct EnemyPlugin;
 
 use rand::Rng;
+use serde::Deserialize;
 
 use crate::{
-    game_state::AppState, level::{Fountain, LevelComponent}, pathfinding::PathfindingAgent,
-    polishing_constants::ENEMY_STRENGTH, MainCamera,
+    gadget::Water,
+    game_state::{unless_frozen, AppState, RunStats},
+    level::{CurrentWaves, Fountain, LevelComponent},
+    pathfinding::PathfindingAgent,
+    polishing_constants::{ENEMY_HEALTH, ENEMY_STRENGTH},
+    wave_asset::{WaveDescription, WaveGroup, WaveScript},
 };
 
-#[derive(Debug, Clone)]
+/// Droplets slower than this count as pooled water.
+const POOL_MAX_SPEED: f32 = 50.0;
+/// How close pooled droplets have to be to a swimmer to speed it up.
+const SWIMMER_POOL_RADIUS: f32 = 100.0;
+/// How many pooled droplets a swimmer needs around it to speed up.
+const SWIMMER_POOL_MIN_DROPLETS: usize = 3;
+const SWIMMER_POOL_SPEED_FACTOR: f32 = 2.0;
+
+/// Progress of the wave that is currently being spawned.
+#[derive(Debug, Clone, Default)]
 pub struct WaveConfig {
-    count_remaining: u32,
-    timer: Timer,
+    groups: Vec<GroupProgress>,
+    elapsed: f32,
+    /// Spawn point of the next enemy from a group without a fixed one.
+    next_spawn_point: usize,
 }
 
-impl Default for WaveConfig {
-    fn default() -> Self {
-        Self {
-            count_remaining: 3,
-            timer: Timer::new(Duration::from_secs(2), true),
-        }
+#[derive(Debug, Clone)]
+struct GroupProgress {
+    group: WaveGroup,
+    spawned: u32,
+}
+
+impl GroupProgress {
+    fn next_spawn_time(&self) -> f32 {
+        self.group.delay + self.spawned as f32 * self.group.interval
     }
 }
 
 impl WaveConfig {
-    pub fn new(count: u32) -> Self {
+    pub fn new(wave: WaveDescription) -> Self {
         WaveConfig {
-            count_remaining: count,
-            timer: Timer::new(Duration::from_secs(2), true),
+            groups: wave
+                .groups
+                .into_iter()
+                .map(|group| GroupProgress { group, spawned: 0 })
+                .collect(),
+            elapsed: 0.0,
+            next_spawn_point: 0,
         }
     }
+
+    pub fn is_finished(&self) -> bool {
+        self.groups
+            .iter()
+            .all(|progress| progress.spawned >= progress.group.count)
+    }
 }
 
-#[derive(Debug, Default)]
+/// Starts spawning wave number `wave` of the current level, counting from 1.
+#[derive(Debug)]
 pub struct SpawnWaveEvent {
-    wave_cfg: WaveConfig,
+    wave: u32,
+}
+
+impl Default for SpawnWaveEvent {
+    fn default() -> Self {
+        SpawnWaveEvent { wave: 1 }
+    }
 }
 
 impl SpawnWaveEvent {
-    pub fn new(count: u32) -> Self {
-        SpawnWaveEvent {
-            wave_cfg: WaveConfig::new(count),
-        }
+    pub fn new(wave: u32) -> Self {
+        SpawnWaveEvent { wave }
     }
 }
 
@@ -57,42 +92,359 @@ fn rand_f32(l: f32, u: f32) -> f32 {
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnWaveEvent>()
+            .add_event::<EnemyKilled>()
             .init_resource::<WaveConfig>()
             .add_system(spawn_new_wave_on_event)
+            .add_system(apply_contact_damage)
+            .add_system(despawn_dead_enemies.after(apply_contact_damage))
+            .add_system(ignite_on_contact)
+            .add_system(
+                update_burning
+                    .with_run_criteria(unless_frozen)
+                    .before(despawn_dead_enemies),
+            )
+            .add_system(resist_knockback)
+            .add_system(update_swimmer_speed)
+            .add_system(update_diggers)
             .add_system_set(SystemSet::on_update(AppState::Attack).with_system(check_for_spawn));
         // Enemy processes.
         // .add_system(fountain_spawns_things);
     }
 }
 
-#[derive(Component, Debug, Default)]
-enum EnemyType {
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EnemyType {
     #[default]
     Grunt,
-    // Swimmer,
-    // Digger,
-    // Tank,
+    /// Faster through pooled water.
+    Swimmer,
+    /// Burrows up through platforms instead of taking the long way.
+    Digger,
+    /// Slow and sturdy, barely pushed around by water.
+    Tank,
+}
+
+pub struct EnemyStats {
+    pub size: f32,
+    pub mass: f32,
+    pub linear_damping: f32,
+    pub move_strength: f32,
+    pub health: f32,
+    /// Share of the velocity change from water hits that gets ignored, from 0.0 to 1.0.
+    pub knockback_resistance: f32,
+    /// Hit points the base loses when this enemy reaches it.
+    pub base_damage: f32,
+    /// Gold the player earns for killing this enemy.
+    pub reward: u32,
+    pub sprite: &'static str,
+    pub tint: Color,
+}
+
+impl EnemyType {
+    pub fn stats(&self) -> EnemyStats {
+        match self {
+            EnemyType::Grunt => EnemyStats {
+                size: 120.0,
+                mass: 1.1,
+                linear_damping: 0.9,
+                move_strength: ENEMY_STRENGTH,
+                health: ENEMY_HEALTH,
+                knockback_resistance: 0.0,
+                base_damage: 10.0,
+                reward: 10,
+                sprite: "enemies/grunt.png",
+                tint: Color::WHITE,
+            },
+            EnemyType::Swimmer => EnemyStats {
+                size: 100.0,
+                mass: 0.8,
+                linear_damping: 0.9,
+                move_strength: ENEMY_STRENGTH * 0.7,
+                health: ENEMY_HEALTH * 0.7,
+                knockback_resistance: 0.0,
+                base_damage: 8.0,
+                reward: 12,
+                sprite: "enemies/grunt.png",
+                tint: Color::rgb(0.5, 0.8, 1.0),
+            },
+            EnemyType::Digger => EnemyStats {
+                size: 110.0,
+                mass: 1.0,
+                linear_damping: 0.9,
+                move_strength: ENEMY_STRENGTH * 0.9,
+                health: ENEMY_HEALTH * 0.8,
+                knockback_resistance: 0.2,
+                base_damage: 15.0,
+                reward: 15,
+                sprite: "enemies/grunt.png",
+                tint: Color::rgb(0.7, 0.5, 0.3),
+            },
+            EnemyType::Tank => EnemyStats {
+                size: 170.0,
+                mass: 3.5,
+                linear_damping: 1.2,
+                move_strength: ENEMY_STRENGTH * 2.5,
+                health: ENEMY_HEALTH * 3.0,
+                knockback_resistance: 0.8,
+                base_damage: 30.0,
+                reward: 40,
+                sprite: "enemies/grunt.png",
+                tint: Color::GRAY,
+            },
+        }
+    }
+
+    /// Enemy types showing up in the given wave, tougher ones join in later waves.
+    pub fn for_wave(wave: u32) -> Vec<EnemyType> {
+        [
+            EnemyType::Grunt,
+            EnemyType::Swimmer,
+            EnemyType::Digger,
+            EnemyType::Tank,
+        ]
+        .into_iter()
+        .take(wave.max(1) as usize)
+        .collect()
+    }
 }
 
 #[derive(Component, Debug, Default)]
 pub struct Enemy;
 
+/// Seconds since startup when the enemy came out of its spawn point.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SpawnedAt(pub f64);
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+/// Damage dealt to an enemy on first contact, e.g. by a water droplet.
+/// Removed after the hit so a droplet only hurts once.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ContactDamage(pub f32);
+
+/// Sets enemies on fire on first contact, e.g. a lava droplet.
+/// Removed after the hit like [`ContactDamage`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BurnOnContact {
+    pub damage_per_second: f32,
+    pub seconds: f32,
+}
+
+/// Damage over time, hitting a burning enemy again starts the burn over.
+#[derive(Component, Debug, Clone)]
+pub struct Burning {
+    damage_per_second: f32,
+    timer: Timer,
+}
+
+/// Cancels part of the velocity change from water hits.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct KnockbackResistance {
+    resistance: f32,
+    last_velocity: Vec2,
+}
+
+/// Sent when an enemy runs out of health, right before it gets despawned.
+#[derive(Debug, Clone)]
+pub struct EnemyKilled {
+    pub enemy_type: EnemyType,
+}
+
+fn apply_contact_damage(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    damage_query: Query<&ContactDamage>,
+    mut health_query: Query<&mut Health, With<Enemy>>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let mut spent = Vec::new();
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _) = collision_event {
+            for (source, target) in [(*a, *b), (*b, *a)] {
+                if spent.contains(&source) {
+                    continue;
+                }
+                if let (Ok(damage), Ok(mut health)) =
+                    (damage_query.get(source), health_query.get_mut(target))
+                {
+                    health.current -= damage.0;
+                    run_stats.shots_hit += 1;
+                    spent.push(source);
+                    commands.entity(source).remove::<ContactDamage>();
+                }
+            }
+        }
+    }
+}
+
+fn ignite_on_contact(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    burn_query: Query<&BurnOnContact>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    let mut spent = Vec::new();
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _) = collision_event {
+            for (source, target) in [(*a, *b), (*b, *a)] {
+                if spent.contains(&source) || !enemy_query.contains(target) {
+                    continue;
+                }
+                if let Ok(burn) = burn_query.get(source) {
+                    commands.entity(target).insert(Burning {
+                        damage_per_second: burn.damage_per_second,
+                        timer: Timer::from_seconds(burn.seconds, false),
+                    });
+                    spent.push(source);
+                    commands.entity(source).remove::<BurnOnContact>();
+                }
+            }
+        }
+    }
+}
+
+fn update_burning(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut burning_query: Query<(Entity, &mut Burning, &mut Health)>,
+) {
+    for (entity, mut burning, mut health) in burning_query.iter_mut() {
+        health.current -= burning.damage_per_second * time.delta_seconds();
+        if burning.timer.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Burning>();
+        }
+    }
+}
+
+fn resist_knockback(
+    mut collision_events: EventReader<CollisionEvent>,
+    water_query: Query<(), With<Water>>,
+    mut enemy_query: Query<(Entity, &mut KnockbackResistance, &mut Velocity)>,
+) {
+    let mut hit = Vec::new();
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _) = collision_event {
+            if water_query.contains(*a) {
+                hit.push(*b);
+            }
+            if water_query.contains(*b) {
+                hit.push(*a);
+            }
+        }
+    }
+
+    for (entity, mut knockback, mut velocity) in enemy_query.iter_mut() {
+        if hit.contains(&entity) {
+            velocity.linvel = knockback
+                .last_velocity
+                .lerp(velocity.linvel, 1.0 - knockback.resistance);
+        }
+        knockback.last_velocity = velocity.linvel;
+    }
+}
+
+fn update_swimmer_speed(
+    water_query: Query<(&Transform, &Velocity), With<Water>>,
+    mut swimmer_query: Query<(&Transform, &EnemyType, &mut PathfindingAgent)>,
+) {
+    for (transform, enemy_type, mut agent) in swimmer_query.iter_mut() {
+        if *enemy_type != EnemyType::Swimmer {
+            continue;
+        }
+
+        let pooled_droplets = water_query
+            .iter()
+            .filter(|(water_transform, water_velocity)| {
+                water_velocity.linvel.length() < POOL_MAX_SPEED
+                    && water_transform.translation.distance(transform.translation)
+                        < SWIMMER_POOL_RADIUS
+            })
+            .count();
+
+        agent.set_speed_factor(if pooled_droplets >= SWIMMER_POOL_MIN_DROPLETS {
+            SWIMMER_POOL_SPEED_FACTOR
+        } else {
+            1.0
+        });
+    }
+}
+
+/// Lets diggers pass through level geometry while they burrow, hidden and without gravity.
+fn update_diggers(
+    mut digger_query: Query<(
+        &EnemyType,
+        &PathfindingAgent,
+        &mut CollisionGroups,
+        &mut GravityScale,
+        &mut Visibility,
+    )>,
+) {
+    for (enemy_type, agent, mut collision_groups, mut gravity_scale, mut visibility) in
+        digger_query.iter_mut()
+    {
+        if *enemy_type != EnemyType::Digger {
+            continue;
+        }
+
+        let digging = agent.is_digging();
+        if visibility.is_visible != digging {
+            continue;
+        }
+
+        visibility.is_visible = !digging;
+        gravity_scale.0 = if digging { 0.0 } else { 1.0 };
+        *collision_groups = if digging {
+            CollisionGroups::new(Group::GROUP_1, Group::NONE)
+        } else {
+            CollisionGroups::new(Group::GROUP_1, Group::GROUP_1 | Group::GROUP_2)
+        };
+    }
+}
+
+fn despawn_dead_enemies(
+    mut commands: Commands,
+    enemy_query: Query<(Entity, &Health, &EnemyType), With<Enemy>>,
+    mut enemy_killed_events: EventWriter<EnemyKilled>,
+) {
+    for (entity, health, enemy_type) in enemy_query.iter() {
+        if health.is_dead() {
+            commands.entity(entity).despawn_recursive();
+            enemy_killed_events.send(EnemyKilled {
+                enemy_type: *enemy_type,
+            });
+        }
+    }
+}
+
 fn spawn_new_wave_on_event(
     mut spawn_wave_events: EventReader<SpawnWaveEvent>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    fountain_query: Query<&Transform, With<Fountain>>,
-    windows: Res<Windows>,
-    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    current_waves: Option<Res<CurrentWaves>>,
+    wave_scripts: Res<Assets<WaveScript>>,
 ) {
-    // Play a sound once per frame if a collision occurred.
-
     for wave_ev in spawn_wave_events.iter() {
-        let mut wave_cfg = wave_ev.wave_cfg.clone();
-        let fountain_pos = fountain_query.single().translation;
-        spawn_enemy_at(&mut commands, &asset_server, fountain_pos, 120.0);
-        wave_cfg.count_remaining -= 1;
-        commands.insert_resource(wave_cfg);
+        let wave = current_waves
+            .as_ref()
+            .and_then(|current_waves| wave_scripts.get(&current_waves.0))
+            .and_then(|script| script.wave(wave_ev.wave))
+            .unwrap_or_else(|| WaveDescription::generated(wave_ev.wave));
+        commands.insert_resource(WaveConfig::new(wave));
     }
 }
 
@@ -101,13 +453,43 @@ fn check_for_spawn(
     mut wave_cfg: ResMut<WaveConfig>,
     asset_server: Res<AssetServer>,
     time: Res<Time>,
-    fountain_query: Query<&Transform, With<Fountain>>,
+    fountain_query: Query<(&Fountain, &Transform)>,
 ) {
-    wave_cfg.timer.tick(time.delta());
-    if wave_cfg.count_remaining > 0 && wave_cfg.timer.just_finished() {
-        let fountain_pos = fountain_query.single().translation;
-        spawn_enemy_at(&mut commands, &asset_server, fountain_pos, 120.0);
-        wave_cfg.count_remaining -= 1;
+    let mut fountains: Vec<(&Fountain, &Transform)> = fountain_query.iter().collect();
+    if wave_cfg.is_finished() || fountains.is_empty() {
+        return;
+    }
+    // keep the alternation order independent of the query order
+    fountains.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+
+    let wave_cfg = &mut *wave_cfg;
+    wave_cfg.elapsed += time.delta_seconds();
+    for progress in wave_cfg.groups.iter_mut() {
+        while progress.spawned < progress.group.count
+            && progress.next_spawn_time() <= wave_cfg.elapsed
+        {
+            let named = progress.group.spawn_point.as_ref().and_then(|name| {
+                let fountain = fountains.iter().find(|(fountain, _)| &fountain.name == name);
+                if fountain.is_none() {
+                    warn!("unknown spawn point {}, alternating instead", name);
+                }
+                fountain
+            });
+            let (_, fountain_transform) = named.copied().unwrap_or_else(|| {
+                let fountain = fountains[wave_cfg.next_spawn_point % fountains.len()];
+                wave_cfg.next_spawn_point += 1;
+                fountain
+            });
+
+            spawn_enemy_at(
+                &mut commands,
+                &asset_server,
+                fountain_transform.translation,
+                progress.group.enemy,
+                time.seconds_since_startup(),
+            );
+            progress.spawned += 1;
+        }
     }
 }
 
@@ -115,53 +497,83 @@ fn fountain_spawns_things(
     mut fountain_query: Query<&Transform, With<Fountain>>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    time: Res<Time>,
 ) {
     if let Some(fountain) = fountain_query.iter_mut().next().map(|x| x.clone()) {
         if rand_f32(0.0, 1.0) > 0.95 {
-            spawn_enemy_at(&mut commands, &asset_server, fountain.translation, 120.0);
+            spawn_enemy_at(
+                &mut commands,
+                &asset_server,
+                fountain.translation,
+                EnemyType::Grunt,
+                time.seconds_since_startup(),
+            );
         }
     }
 }
 
-fn spawn_enemy_at(commands: &mut Commands, asset_server: &Res<AssetServer>, pos: Vec3, size: f32) {
+fn spawn_enemy_at(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    pos: Vec3,
+    enemy_type: EnemyType,
+    spawned_at: f64,
+) {
+    let stats = enemy_type.stats();
     commands
         .spawn()
         .insert(RigidBody::Dynamic)
         .insert(Collider::ball(0.5))
+        .insert(ColliderMassProperties::Mass(stats.mass))
         .insert(CollisionGroups::new(
             Group::GROUP_1,
             Group::GROUP_1 | Group::GROUP_2,
         ))
         .insert(Damping {
-            linear_damping: 0.90,
+            linear_damping: stats.linear_damping,
             angular_damping: 0.5,
         })
         .insert(ExternalForce {
             force: Vec2::new(0.0, 0.0),
             torque: 0.0,
         })
+        .insert(Velocity::default())
+        .insert(GravityScale(1.0))
+        .insert(KnockbackResistance {
+            resistance: stats.knockback_resistance,
+            last_velocity: Vec2::ZERO,
+        })
         .insert(Enemy)
-        .insert(EnemyType::Grunt)
-        .insert(PathfindingAgent::new(ENEMY_STRENGTH))
+        .insert(enemy_type)
+        .insert(Health::new(stats.health))
+        .insert(SpawnedAt(spawned_at))
+        .insert(PathfindingAgent::new(
+            stats.move_strength,
+            enemy_type == EnemyType::Digger,
+        ))
         .insert(LevelComponent)
         .insert_bundle(SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(Vec2::splat(1.0)),
+                color: stats.tint,
                 ..default()
             },
-            texture: asset_server.load("enemies/grunt.png"),
-            transform: Transform::from_scale(Vec3::new(size, size, 1.0)).with_translation(pos),
+            texture: asset_server.load(stats.sprite),
+            transform: Transform::from_scale(Vec3::new(stats.size, stats.size, 1.0))
+                .with_translation(pos),
             ..default()
         })
         // what is this for?
         .insert(
             Sprite {
                 custom_size: Some(Vec2::splat(1.0)),
+                color: stats.tint,
                 ..default()
             }
             .ease_to(
                 Sprite {
                     custom_size: Some(vec2(1.3, 0.7)),
+                    color: stats.tint,
                     ..default()
                 },
                 EaseFunction::CubicInOut,
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::{
+    navmesh::{EdgeKind, Navmesh},
+    WORLD_SIZE,
+};
+
+const CELL_SIZE: f32 = 50.0;
+/// How many cells around a navmesh edge get directions, so agents slightly off it still find their way back.
+const CORRIDOR_RADIUS: i32 = 1;
+/// How many cells away from an agent to look for a direction when its own cell has none.
+const SEARCH_RADIUS: i32 = 3;
+
+#[derive(Debug, Clone, Copy)]
+struct FlowCell {
+    cost: f32,
+    target: Vec2,
+}
+
+/// Grid over the level where every cell along the navmesh knows where to head next
+/// to reach the base. Computed once per level and shared by all agents.
+#[derive(Default)]
+pub struct FlowField {
+    width: i32,
+    height: i32,
+    cells: Vec<Option<FlowCell>>,
+}
+
+impl FlowField {
+    pub fn from_navmesh(navmesh: &Navmesh) -> Self {
+        let width = (WORLD_SIZE.x / CELL_SIZE).ceil() as i32;
+        let height = (WORLD_SIZE.y / CELL_SIZE).ceil() as i32;
+        let mut field = FlowField {
+            width,
+            height,
+            cells: vec![None; (width * height) as usize],
+        };
+
+        let costs = navmesh.costs_to_goal();
+        for (from, edges) in navmesh.edges.iter().enumerate() {
+            for edge in edges.iter() {
+                // only edges that lead closer to the base, and everyone can walk
+                if edge.kind == EdgeKind::Dig
+                    || !costs[edge.to].is_finite()
+                    || costs[edge.to] >= costs[from]
+                {
+                    continue;
+                }
+                field.rasterize_edge(navmesh.nodes[from], navmesh.nodes[edge.to], costs[edge.to]);
+            }
+        }
+
+        field
+    }
+
+    /// Point the agent at `position` should head for, if it is close enough to the field.
+    pub fn sample(&self, position: Vec2) -> Option<Vec2> {
+        let (x, y) = self.cell_coords(position);
+        if let Some(cell) = self.cell(x, y) {
+            return Some(cell.target);
+        }
+
+        // head back onto the field through the cheapest cell nearby
+        self.cheapest_cell_nearby(x, y).map(|(_, center)| center)
+    }
+
+    /// Length of the way to the base from `position`, if it is close enough to the field.
+    pub fn cost_at(&self, position: Vec2) -> Option<f32> {
+        let (x, y) = self.cell_coords(position);
+        if let Some(cell) = self.cell(x, y) {
+            return Some(cell.cost);
+        }
+
+        self.cheapest_cell_nearby(x, y)
+            .map(|(cost, center)| cost + position.distance(center))
+    }
+
+    /// Cost and center of the cheapest cell within `SEARCH_RADIUS` of cell `x`, `y`.
+    fn cheapest_cell_nearby(&self, x: i32, y: i32) -> Option<(f32, Vec2)> {
+        let mut best: Option<(f32, Vec2)> = None;
+        for dy in -SEARCH_RADIUS..=SEARCH_RADIUS {
+            for dx in -SEARCH_RADIUS..=SEARCH_RADIUS {
+                if let Some(cell) = self.cell(x + dx, y + dy) {
+                    if best.is_none_or(|(cost, _)| cell.cost < cost) {
+                        best = Some((cell.cost, self.cell_center(x + dx, y + dy)));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn rasterize_edge(&mut self, from: Vec2, to: Vec2, to_cost: f32) {
+        let steps = (from.distance(to) / (CELL_SIZE * 0.5)).ceil().max(1.0) as usize;
+
+        for step in 0..=steps {
+            let (x, y) = self.cell_coords(from.lerp(to, step as f32 / steps as f32));
+
+            for dy in -CORRIDOR_RADIUS..=CORRIDOR_RADIUS {
+                for dx in -CORRIDOR_RADIUS..=CORRIDOR_RADIUS {
+                    let cost = to_cost + self.cell_center(x + dx, y + dy).distance(to);
+                    if let Some(idx) = self.index(x + dx, y + dy) {
+                        if self.cells[idx].is_none_or(|cell| cost < cell.cost) {
+                            self.cells[idx] = Some(FlowCell { cost, target: to });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn cell_coords(&self, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / CELL_SIZE).floor() as i32,
+            (position.y / CELL_SIZE).floor() as i32,
+        )
+    }
+
+    fn cell_center(&self, x: i32, y: i32) -> Vec2 {
+        (Vec2::new(x as f32, y as f32) + 0.5) * CELL_SIZE
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y * self.width + x) as usize)
+    }
+
+    fn cell(&self, x: i32, y: i32) -> Option<FlowCell> {
+        self.index(x, y).and_then(|idx| self.cells[idx])
+    }
+}
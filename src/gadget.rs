@@ -1,22 +1,59 @@
-use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
 use bevy_rapier2d::prelude::*;
 
 use crate::{
-    game_state::{AppState, WaveControler},
-    input::get_world_cursor_pos,
+    droplet_pool::{Droplet, DropletPool},
+    enemy::{BurnOnContact, ContactDamage},
+    gadget_catalog::{GadgetKind, Projectile},
+    game_state::{unless_frozen, AppState, HudMessage, RunStats, Shop},
+    input::{get_world_cursor_pos, WorldCursor},
     level::LevelComponent,
-    polishing_constants::{WATER_SIZE, WATER_STRENGTH},
+    level_asset::{ChunkDescription, ChunkKind},
+    polishing_constants::{
+        GADGET_MIN_DISTANCE, LAVA_BURN_DPS, LAVA_BURN_SECS, LAVA_DAMAGE,
+        PROJECTILE_SPEED_PER_STRENGTH, SELL_REFUND_FRACTION, WATER_DAMAGE, WATER_SIZE,
+        WATER_STRENGTH,
+    },
+    targeting::TargetingMode,
     MainCamera,
 };
 
+/// Starts placing a new gadget of the given kind at the cursor.
 #[derive(Debug, Default)]
-pub struct SpawnCannonGadgetEvent;
+pub struct SpawnGadgetEvent {
+    pub kind: GadgetKind,
+}
+
+/// Droplets hit enemies and each other, as well as the level which is in every group.
+pub const DROPLET_COLLISION_GROUPS: CollisionGroups =
+    CollisionGroups::new(Group::GROUP_2, Group::GROUP_1.union(Group::GROUP_2));
 
 pub struct GadgetPlugin;
 
 #[derive(Component, Default)]
 pub struct Gadget {
     pub is_placed: bool,
+    /// Gold spent on the gadget, the base for refunds.
+    pub paid: u32,
+    /// Where the gadget was picked up from while it is being moved.
+    pub origin: Option<Vec3>,
+    /// Index into the upgrade tiers of the gadget's kind, stays with the gadget when moved.
+    pub tier: usize,
+}
+
+impl Gadget {
+    /// Whether `position` is on the gadget's sprite, which is drawn at half its scale.
+    fn contains(transform: &Transform, position: Vec2) -> bool {
+        let half_extents = transform.scale.truncate() * 0.25;
+        (transform.translation.truncate() - position)
+            .abs()
+            .cmple(half_extents)
+            .all()
+    }
 }
 
 #[derive(Component, Default)]
@@ -27,51 +64,122 @@ pub struct GadgetPart {
 #[derive(Component, Default)]
 pub struct Water;
 
+#[derive(Component, Default)]
+pub struct Lava;
+
+/// Cooled down lava in the way of enemies, left behind when water hits lava.
+#[derive(Component)]
+pub struct Obsidian {
+    lifetime: Timer,
+}
+
+/// Mesh and material shared by all obsidian blocks.
+pub struct ObsidianAssets {
+    mesh: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ObsidianAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Quad::default().into())
+            .into();
+        let material = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(ColorMaterial::from(Color::hex("1E1428").unwrap()));
+
+        ObsidianAssets { mesh, material }
+    }
+}
+
 #[derive(Component)]
 pub struct CannonGadget {
-    emission_strength: f32,
-    shots_per_second: f32,
+    pub emission_strength: f32,
+    pub shots_per_second: f32,
+    /// How far away enemies can be to get shot at.
+    pub range: f32,
+    pub projectile_size: f32,
+    /// Time until the cannon can fire again.
+    pub cooldown: Timer,
+}
+
+impl CannonGadget {
+    /// Stats of a gadget of the given kind, upgraded to `tier`.
+    pub fn new(kind: GadgetKind, tier: usize) -> Self {
+        let definition = kind.definition();
+        let tier = &definition.tiers[tier.min(definition.tiers.len() - 1)];
+        let shots_per_second = definition.shots_per_second * tier.fire_rate_factor;
+
+        CannonGadget {
+            emission_strength: definition.emission_strength * tier.strength_factor,
+            shots_per_second,
+            range: GADGET_MIN_DISTANCE * tier.range_factor,
+            projectile_size: WATER_SIZE * tier.size_factor,
+            cooldown: Timer::from_seconds(1.0 / shots_per_second, false),
+        }
+    }
 }
 
 const SNAP_ON_DIST: f32 = 300.0;
+const OBSIDIAN_SIZE: f32 = 45.0;
+/// How long an obsidian block blocks the way before it crumbles.
+const OBSIDIAN_SECS: f32 = 8.0;
+/// Height of a placed gadget's center above the surface it stands on.
+const SNAP_SURFACE_OFFSET: f32 = 100.0;
 
 impl Plugin for GadgetPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<SpawnCannonGadgetEvent>()
+        app.add_event::<SpawnGadgetEvent>()
+            .init_resource::<SnapSurfaces>()
+            .init_resource::<ObsidianAssets>()
+            .add_system(cool_lava_in_water)
+            .add_system(crumble_obsidian.with_run_criteria(unless_frozen))
+            .add_system(update_snap_surfaces)
             // .add_system(shoot_water_system)
             .add_system(on_gadget_placment_status_change)
-            .add_system(handle_spawn_cannons)
-            .add_system(update_gadget_placement);
+            // clicks on the pause or game over menus must not place gadgets
+            .add_system(update_gadget_placement.with_run_criteria(unless_frozen))
+            .add_system_set(
+                SystemSet::on_update(AppState::Build)
+                    .with_system(handle_spawn_gadgets)
+                    .with_system(handle_gadget_right_click)
+                    .with_system(upgrade_gadget_under_cursor)
+                    .with_system(cycle_targeting_mode),
+            );
     }
 }
 
-fn handle_spawn_cannons(
+fn handle_spawn_gadgets(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    spawn_cannon_events: EventReader<SpawnCannonGadgetEvent>,
-    app_state: Res<State<AppState>>,
-    windows: Res<Windows>,
-    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut spawn_gadget_events: EventReader<SpawnGadgetEvent>,
+    cursor: WorldCursor,
     asset_server: Res<AssetServer>,
-    cannon_query: Query<Entity, With<Gadget>>,
-    wave_controller: ResMut<WaveControler>,
+    snap_surfaces: Res<SnapSurfaces>,
+    mut shop: Shop,
+    mut gadget_query: Query<(&mut Gadget, &Transform)>,
 ) {
-    if spawn_cannon_events.is_empty()
-        || *app_state.current() != AppState::Build
-        || cannon_query.iter().len() > wave_controller.wave_size as usize
-    {
-        return;
-    }
+    let kind = match spawn_gadget_events.iter().last() {
+        Some(spawn_gadget_event) => spawn_gadget_event.kind,
+        None => return,
+    };
 
-    spawn_cannon_events.clear();
+    let definition = kind.definition();
+    if let Some(position) = cursor.position() {
+        // clicking a placed gadget picks it back up instead of building a new one
+        if let Some((mut gadget, transform)) = gadget_query
+            .iter_mut()
+            .find(|(gadget, transform)| gadget.is_placed && Gadget::contains(transform, position))
+        {
+            gadget.is_placed = false;
+            gadget.origin = Some(transform.translation);
+            return;
+        }
 
-    if let Some(position) = get_world_cursor_pos(windows, camera_q) {
-        if let Some(position) = snap_to_surface(position) {
-            let cannon_component = CannonGadget {
-                emission_strength: 10.0,
-                shots_per_second: 10.0,
-            };
+        if let Some(position) = snap_to_surface(position, &snap_surfaces, definition.surfaces) {
+            if !shop.try_buy(&format!("a {}", definition.name), definition.cost) {
+                return;
+            }
 
             commands
                 .spawn()
@@ -80,7 +188,7 @@ fn handle_spawn_cannons(
                         custom_size: Some(Vec2::splat(1.0)),
                         ..default()
                     },
-                    texture: asset_server.load("items/CanonWater.png"),
+                    texture: asset_server.load(definition.sprite),
                     transform: Transform::from_scale(Vec3::new(379.0, 512.0, 1.0))
                         .with_translation(Vec3::ONE),
                     ..default()
@@ -89,8 +197,15 @@ fn handle_spawn_cannons(
                     custom_size: Some(Vec2::splat(0.5)),
                     ..default()
                 })
-                // .insert(cannon_component)
-                .insert(Gadget { is_placed: false })
+                .insert(CannonGadget::new(kind, 0))
+                .insert(TargetingMode::default())
+                .insert(kind)
+                .insert(Gadget {
+                    is_placed: false,
+                    paid: definition.cost,
+                    origin: None,
+                    tier: 0,
+                })
                 .insert(LevelComponent)
                 .insert(GadgetPart { is_placed: false });
         }
@@ -98,19 +213,24 @@ fn handle_spawn_cannons(
 }
 
 fn update_gadget_placement(
-    mut commands: Commands,
-    mut gadget_query: Query<(&mut Gadget, &mut Transform)>,
-    windows: Res<Windows>,
-    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut gadget_query: Query<(&mut Gadget, &GadgetKind, &mut Transform)>,
+    cursor: WorldCursor,
     mouse_buttons: Res<Input<MouseButton>>,
+    snap_surfaces: Res<SnapSurfaces>,
+    mut run_stats: ResMut<RunStats>,
 ) {
-    if let Some(position) = get_world_cursor_pos(windows, camera_q) {
-        for (mut gadget, mut gadget_transform) in &mut gadget_query {
-            if mouse_buttons.just_released(MouseButton::Left) {
+    if let Some(position) = cursor.position() {
+        for (mut gadget, kind, mut gadget_transform) in &mut gadget_query {
+            if mouse_buttons.just_released(MouseButton::Left) && !gadget.is_placed {
                 gadget.is_placed = true;
+                // moved gadgets were counted when they were first built
+                if gadget.origin.take().is_none() {
+                    run_stats.gadgets_built += 1;
+                }
             }
             if !gadget.is_placed {
-                if let Some(position) = snap_to_surface(position) {
+                let surfaces = kind.definition().surfaces;
+                if let Some(position) = snap_to_surface(position, &snap_surfaces, surfaces) {
                     gadget_transform.translation = Vec3::new(position.x, position.y, 1.0);
                 }
             }
@@ -118,6 +238,145 @@ fn update_gadget_placement(
     }
 }
 
+/// Right-click cancels the gadget following the cursor, or sells the placed gadget under it.
+fn handle_gadget_right_click(
+    mut commands: Commands,
+    mouse_buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut gadget_query: Query<(Entity, &mut Gadget, &mut Transform)>,
+    mut shop: Shop,
+) {
+    if !mouse_buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+
+    let mut cancelled = false;
+    for (entity, mut gadget, mut transform) in gadget_query.iter_mut() {
+        if gadget.is_placed {
+            continue;
+        }
+
+        cancelled = true;
+        match gadget.origin.take() {
+            // a moved gadget goes back to where it was
+            Some(origin) => {
+                transform.translation = origin;
+                gadget.is_placed = true;
+            }
+            None => {
+                shop.wallet.balance += gadget.paid;
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+
+    if cancelled {
+        return;
+    }
+
+    if let Some(position) = get_world_cursor_pos(windows, camera_q) {
+        if let Some((entity, gadget, _)) = gadget_query
+            .iter()
+            .find(|(_, _, transform)| Gadget::contains(transform, position))
+        {
+            let refund = (gadget.paid as f32 * SELL_REFUND_FRACTION).round() as u32;
+            shop.wallet.balance += refund;
+            shop.hud_message
+                .show(format!("sold gadget for {} gold", refund));
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// U buys the next upgrade tier for the placed gadget under the cursor.
+fn upgrade_gadget_under_cursor(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut gadget_query: Query<(
+        &mut Gadget,
+        &GadgetKind,
+        &Transform,
+        &mut CannonGadget,
+        &mut Sprite,
+    )>,
+    mut shop: Shop,
+) {
+    if !keys.just_pressed(KeyCode::U) {
+        return;
+    }
+
+    let position = match get_world_cursor_pos(windows, camera_q) {
+        Some(position) => position,
+        None => return,
+    };
+
+    for (mut gadget, kind, transform, mut cannon, mut sprite) in gadget_query.iter_mut() {
+        if !gadget.is_placed || !Gadget::contains(transform, position) {
+            continue;
+        }
+
+        let definition = kind.definition();
+        let next_tier = match definition.tiers.get(gadget.tier + 1) {
+            Some(next_tier) => next_tier,
+            None => {
+                shop.hud_message
+                    .show(format!("the {} is fully upgraded", definition.name));
+                return;
+            }
+        };
+
+        if !shop.try_buy(
+            &format!("upgrading the {}", definition.name),
+            next_tier.cost,
+        ) {
+            return;
+        }
+
+        gadget.tier += 1;
+        gadget.paid += next_tier.cost;
+        *cannon = CannonGadget::new(*kind, gadget.tier);
+        sprite.color = next_tier.tint;
+        shop.hud_message.show(format!(
+            "upgraded the {} to tier {}",
+            definition.name,
+            gadget.tier + 1
+        ));
+        return;
+    }
+}
+
+/// T switches the placed gadget under the cursor to the next targeting mode.
+fn cycle_targeting_mode(
+    keys: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut gadget_query: Query<(&Gadget, &GadgetKind, &Transform, &mut TargetingMode)>,
+    mut hud_message: ResMut<HudMessage>,
+) {
+    if !keys.just_pressed(KeyCode::T) {
+        return;
+    }
+
+    let position = match get_world_cursor_pos(windows, camera_q) {
+        Some(position) => position,
+        None => return,
+    };
+
+    for (gadget, kind, transform, mut mode) in gadget_query.iter_mut() {
+        if gadget.is_placed && Gadget::contains(transform, position) {
+            *mode = mode.next();
+            hud_message.show(format!(
+                "the {} now targets the {} enemy",
+                kind.definition().name,
+                mode.name()
+            ));
+            return;
+        }
+    }
+}
+
 fn on_gadget_placment_status_change(
     mut color_materials: ResMut<Assets<ColorMaterial>>,
     changed_gadget_part_query: Query<(&Handle<ColorMaterial>, &GadgetPart), Changed<GadgetPart>>,
@@ -130,33 +389,58 @@ fn on_gadget_placment_status_change(
     }
 }
 
-fn snap_to_surface(sample_point: Vec2) -> Option<Vec2> {
-    struct Edge {
-        left: Vec2,
-        right: Vec2,
+/// A surface gadgets can be placed on, running from `left` to `right`.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    left: Vec2,
+    right: Vec2,
+    kind: ChunkKind,
+}
+
+/// Surfaces gadgets snap to, derived from the walkable chunks and slopes of the current level.
+#[derive(Debug, Default)]
+pub struct SnapSurfaces {
+    edges: Vec<Edge>,
+}
+
+fn update_snap_surfaces(
+    mut snap_surfaces: ResMut<SnapSurfaces>,
+    added_chunk_query: Query<(), Added<ChunkKind>>,
+    chunk_query: Query<&ChunkDescription>,
+) {
+    if added_chunk_query.is_empty() {
+        return;
     }
 
-    let snap_edges: Vec<Edge> = vec![
-        Edge {
-            left: Vec2::new(35.0, 185.0),
-            right: Vec2::new(3165.0, 185.0),
-        },
-        Edge {
-            left: Vec2::new(35.0, 835.0),
-            right: Vec2::new(650.0, 835.0),
-        },
-        Edge {
-            left: Vec2::new(400.0, 1430.0),
-            right: Vec2::new(1100.0, 1430.0),
-        },
-        Edge {
-            left: Vec2::new(1100.0, 1820.0),
-            right: Vec2::new(2000.0, 1820.0),
-        },
-    ];
+    snap_surfaces.edges = chunk_query
+        .iter()
+        .filter(|chunk| matches!(chunk.kind, ChunkKind::Walkable | ChunkKind::Slope))
+        .map(|chunk| {
+            // the same surface the navmesh walks on
+            let (left, right) = chunk.top_surface();
+            let offset = Vec2::new(0.0, SNAP_SURFACE_OFFSET);
+
+            Edge {
+                left: left + offset,
+                right: right + offset,
+                kind: chunk.kind,
+            }
+        })
+        .collect();
+}
+
+fn snap_to_surface(
+    sample_point: Vec2,
+    snap_surfaces: &SnapSurfaces,
+    allowed: &[ChunkKind],
+) -> Option<Vec2> {
+    let mut closest: Option<(f32, Vec2)> = None;
 
-    for edge in snap_edges {
-        if sample_point.x < edge.left.x || sample_point.x > edge.right.x {
+    for edge in snap_surfaces.edges.iter() {
+        if !allowed.contains(&edge.kind)
+            || sample_point.x < edge.left.x
+            || sample_point.x > edge.right.x
+        {
             continue;
         }
 
@@ -164,30 +448,28 @@ fn snap_to_surface(sample_point: Vec2) -> Option<Vec2> {
 
         let snap_on_target = edge.right.lerp(edge.left, t);
         let dist = (sample_point.y - snap_on_target.y).abs();
-        if dist < SNAP_ON_DIST {
-            dbg!(t);
-            return Some(snap_on_target);
+        if dist < SNAP_ON_DIST && closest.is_none_or(|(closest_dist, _)| dist < closest_dist) {
+            closest = Some((dist, snap_on_target));
         }
     }
 
-    None
+    closest.map(|(_, snap_on_target)| snap_on_target)
 }
 
 fn shoot_water_system(
     buttons: Res<Input<MouseButton>>,
     windows: Res<Windows>,
     camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut droplet_pool: ResMut<DropletPool>,
     mut commands: Commands,
 ) {
     if buttons.pressed(MouseButton::Left) {
         if let Some(position) = get_world_cursor_pos(windows, camera_q) {
             shoot_water(
                 Vec3::new(position.x, position.y, 0.0),
-                Vec3::new(position.x, position.y, 0.0) + Vec3::NEG_ONE,
-                &mut meshes,
-                &mut materials,
+                Vec2::NEG_ONE.normalize() * WATER_STRENGTH * PROJECTILE_SPEED_PER_STRENGTH,
+                WATER_SIZE,
+                &mut droplet_pool,
                 &mut commands,
             );
         }
@@ -196,31 +478,134 @@ fn shoot_water_system(
 
 pub fn shoot_water(
     shoot_pos: Vec3,
-    target_pos: Vec3,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
+    velocity: Vec2,
+    size: f32,
+    droplet_pool: &mut DropletPool,
     commands: &mut Commands,
 ) {
-    commands
-        .spawn()
+    shoot_droplet(
+        shoot_pos,
+        velocity,
+        size,
+        Projectile::Water,
+        droplet_pool,
+        commands,
+    )
+    .insert(ContactDamage(WATER_DAMAGE))
+    .insert(Water);
+}
+
+pub fn shoot_lava(
+    shoot_pos: Vec3,
+    velocity: Vec2,
+    size: f32,
+    droplet_pool: &mut DropletPool,
+    commands: &mut Commands,
+) {
+    shoot_droplet(
+        shoot_pos,
+        velocity,
+        size,
+        Projectile::Lava,
+        droplet_pool,
+        commands,
+    )
+    .insert(ContactDamage(LAVA_DAMAGE))
+    .insert(BurnOnContact {
+        damage_per_second: LAVA_BURN_DPS,
+        seconds: LAVA_BURN_SECS,
+    })
+    .insert(Lava);
+}
+
+/// Shoots a droplet from the pool, reusing its entity if one is free.
+fn shoot_droplet<'w, 's, 'a>(
+    shoot_pos: Vec3,
+    velocity: Vec2,
+    size: f32,
+    projectile: Projectile,
+    droplet_pool: &mut DropletPool,
+    commands: &'a mut Commands<'w, 's>,
+) -> EntityCommands<'w, 's, 'a> {
+    let entity = droplet_pool.acquire(commands);
+    let mut droplet = commands.entity(entity);
+    droplet
         .insert(RigidBody::Dynamic)
+        // wakes up recycled droplets
+        .insert(Sleeping::default())
         .insert(Collider::ball(0.5))
-        .insert(CollisionGroups::new(
-            Group::GROUP_2,
-            Group::GROUP_1 | Group::GROUP_2,
-        ))
+        .insert(DROPLET_COLLISION_GROUPS)
+        .insert(GravityScale(1.0))
         .insert(Restitution::coefficient(0.1))
-        .insert(ExternalImpulse {
-            impulse: (target_pos - shoot_pos).truncate().normalize() * WATER_STRENGTH,
-            torque_impulse: 0.0,
+        .insert(ActiveEvents::COLLISION_EVENTS)
+        .insert(Velocity::linear(velocity))
+        .insert(Droplet::default())
+        .insert_bundle(MaterialMesh2dBundle {
+            mesh: droplet_pool.mesh(),
+            transform: Transform::from_xyz(shoot_pos.x, shoot_pos.y, 0.96)
+                .with_scale(Vec3::splat(size)),
+            material: droplet_pool.material(projectile),
+            ..default()
+        });
+    droplet
+}
+
+/// Water hitting lava evaporates and leaves the lava behind as a block of obsidian.
+fn cool_lava_in_water(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    obsidian_assets: Res<ObsidianAssets>,
+    mut droplet_pool: ResMut<DropletPool>,
+    lava_query: Query<&Transform, With<Lava>>,
+    water_query: Query<(), With<Water>>,
+) {
+    let mut cooled = Vec::new();
+
+    for collision_event in collision_events.iter() {
+        if let CollisionEvent::Started(a, b, _) = collision_event {
+            for (lava, water) in [(*a, *b), (*b, *a)] {
+                if cooled.contains(&lava) || cooled.contains(&water) || !water_query.contains(water)
+                {
+                    continue;
+                }
+                if let Ok(lava_transform) = lava_query.get(lava) {
+                    cooled.push(lava);
+                    cooled.push(water);
+                    droplet_pool.release(lava, &mut commands);
+                    droplet_pool.release(water, &mut commands);
+                    spawn_obsidian(lava_transform.translation, &obsidian_assets, &mut commands);
+                }
+            }
+        }
+    }
+}
+
+fn spawn_obsidian(position: Vec3, obsidian_assets: &ObsidianAssets, commands: &mut Commands) {
+    commands
+        .spawn()
+        .insert(RigidBody::Fixed)
+        .insert(Collider::cuboid(0.5, 0.5))
+        .insert(Obsidian {
+            lifetime: Timer::from_seconds(OBSIDIAN_SECS, false),
         })
         .insert(LevelComponent)
-        .insert(Water)
         .insert_bundle(MaterialMesh2dBundle {
-            mesh: meshes.add(Mesh::from(shape::Circle::default())).into(),
-            transform: Transform::from_xyz(shoot_pos.x, shoot_pos.y, 0.96)
-                .with_scale(Vec3::splat(WATER_SIZE)),
-            material: materials.add(ColorMaterial::from(Color::hex("27636E").unwrap())),
+            mesh: obsidian_assets.mesh.clone(),
+            transform: Transform::from_xyz(position.x, position.y, 0.96)
+                .with_scale(Vec3::splat(OBSIDIAN_SIZE)),
+            material: obsidian_assets.material.clone(),
             ..default()
         });
 }
+
+fn crumble_obsidian(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut obsidian_query: Query<(Entity, &mut Obsidian)>,
+) {
+    for (entity, mut obsidian) in obsidian_query.iter_mut() {
+        if obsidian.lifetime.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
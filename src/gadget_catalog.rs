@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use crate::{
+    level_asset::ChunkKind,
+    polishing_constants::{
+        CANNON_SHOTS_PER_SECOND, LAVA_SHOTS_PER_SECOND, LAVA_STRENGTH, WATER_STRENGTH,
+    },
+};
+
+/// Everything that can be built, in toolbar order. Hotkeys 1 to 9 follow this order.
+pub const GADGET_KINDS: [GadgetKind; 2] = [GadgetKind::WaterCannon, GadgetKind::LavaCannon];
+
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GadgetKind {
+    #[default]
+    WaterCannon,
+    /// Slow, heavy shots that set enemies on fire.
+    LavaCannon,
+}
+
+/// What a gadget shoots at enemies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projectile {
+    Water,
+    Lava,
+}
+
+/// An upgrade step of a gadget, with its stats relative to the untouched gadget.
+pub struct GadgetTier {
+    /// Gold it takes to upgrade to this tier.
+    pub cost: u32,
+    pub fire_rate_factor: f32,
+    pub strength_factor: f32,
+    /// Scales the targeting range, which starts at `GADGET_MIN_DISTANCE`.
+    pub range_factor: f32,
+    /// Scales the projectile size, which starts at `WATER_SIZE`.
+    pub size_factor: f32,
+    pub tint: Color,
+}
+
+const BASE_TIER: GadgetTier = GadgetTier {
+    cost: 0,
+    fire_rate_factor: 1.0,
+    strength_factor: 1.0,
+    range_factor: 1.0,
+    size_factor: 1.0,
+    tint: Color::WHITE,
+};
+
+static WATER_CANNON_TIERS: [GadgetTier; 3] = [
+    BASE_TIER,
+    GadgetTier {
+        cost: 60,
+        fire_rate_factor: 1.3,
+        strength_factor: 1.15,
+        range_factor: 1.15,
+        size_factor: 1.2,
+        tint: Color::rgb(0.7, 0.9, 1.0),
+    },
+    GadgetTier {
+        cost: 120,
+        fire_rate_factor: 1.7,
+        strength_factor: 1.3,
+        range_factor: 1.3,
+        size_factor: 1.4,
+        tint: Color::rgb(0.45, 0.75, 1.0),
+    },
+];
+
+static LAVA_CANNON_TIERS: [GadgetTier; 3] = [
+    BASE_TIER,
+    GadgetTier {
+        cost: 90,
+        fire_rate_factor: 1.25,
+        strength_factor: 1.15,
+        range_factor: 1.1,
+        size_factor: 1.2,
+        tint: Color::rgb(1.0, 0.8, 0.6),
+    },
+    GadgetTier {
+        cost: 160,
+        fire_rate_factor: 1.5,
+        strength_factor: 1.3,
+        range_factor: 1.2,
+        size_factor: 1.4,
+        tint: Color::rgb(1.0, 0.55, 0.4),
+    },
+];
+
+pub struct GadgetDefinition {
+    pub name: &'static str,
+    pub sprite: &'static str,
+    pub cost: u32,
+    pub projectile: Projectile,
+    pub emission_strength: f32,
+    pub shots_per_second: f32,
+    /// Kinds of level chunks the gadget can be placed on.
+    pub surfaces: &'static [ChunkKind],
+    /// Upgrade tiers, starting with the one the gadget is built with.
+    pub tiers: &'static [GadgetTier],
+}
+
+impl GadgetKind {
+    pub fn definition(&self) -> GadgetDefinition {
+        match self {
+            GadgetKind::WaterCannon => GadgetDefinition {
+                name: "Water cannon",
+                sprite: "items/CanonWater.png",
+                cost: 50,
+                projectile: Projectile::Water,
+                emission_strength: WATER_STRENGTH,
+                shots_per_second: CANNON_SHOTS_PER_SECOND,
+                surfaces: &[ChunkKind::Walkable],
+                tiers: &WATER_CANNON_TIERS,
+            },
+            GadgetKind::LavaCannon => GadgetDefinition {
+                name: "Lava cannon",
+                sprite: "items/CanonLava.png",
+                cost: 80,
+                projectile: Projectile::Lava,
+                emission_strength: LAVA_STRENGTH,
+                shots_per_second: LAVA_SHOTS_PER_SECOND,
+                surfaces: &[ChunkKind::Walkable, ChunkKind::Slope],
+                tiers: &LAVA_CANNON_TIERS,
+            },
+        }
+    }
+
+    /// The gadget picked with the number key `hotkey`, counting from 1.
+    pub fn from_hotkey(hotkey: usize) -> Option<GadgetKind> {
+        hotkey
+            .checked_sub(1)
+            .and_then(|idx| GADGET_KINDS.get(idx))
+            .copied()
+    }
+}
+
+/// Gadget created by the next placement in the build phase.
+#[derive(Debug, Default)]
+pub struct SelectedGadget(pub GadgetKind);
@@ -0,0 +1,155 @@
+use bevy::{app::AppExit, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game_state::{reset_run, AppState, RunStats, Wallet, WaveControler},
+    level::LevelComponent,
+    menu::{border, button, button_text, menu_background, overlay},
+};
+
+pub struct GameOverPlugin;
+
+#[derive(Component)]
+struct GameOverUi;
+
+#[derive(Component, Debug)]
+enum GameOverButton {
+    Retry,
+    MainMenu,
+    Quit,
+}
+
+/// Buttons of the results screen whose interaction changed this frame.
+type GameOverButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static GameOverButton),
+    (Changed<Interaction>, With<Button>),
+>;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::GameOver)
+                .with_system(freeze_simulation)
+                .with_system(spawn_results_screen),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::GameOver).with_system(game_over_button_system),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(cleanup_run));
+    }
+}
+
+fn freeze_simulation(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+/// Leaving the results screen always ends the run, whichever button was pressed.
+fn cleanup_run(
+    mut commands: Commands,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    mut wave_controler: ResMut<WaveControler>,
+    mut run_stats: ResMut<RunStats>,
+    mut wallet: ResMut<Wallet>,
+    ui_query: Query<Entity, With<GameOverUi>>,
+    level_query: Query<Entity, With<LevelComponent>>,
+) {
+    for entity in ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    rapier_config.physics_pipeline_active = true;
+    reset_run(
+        &mut commands,
+        level_query.iter(),
+        &mut wave_controler,
+        &mut run_stats,
+        &mut wallet,
+    );
+}
+
+fn results_text(asset_server: &Res<AssetServer>, label: &str, font_size: f32) -> TextBundle {
+    TextBundle {
+        style: Style {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..Default::default()
+        },
+        text: Text::from_section(
+            label,
+            TextStyle {
+                font: asset_server.load("fonts/Oswald-SemiBold.ttf"),
+                font_size,
+                color: Color::hex("45A7BA").unwrap(),
+            },
+        ),
+        ..Default::default()
+    }
+}
+
+fn spawn_results_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    run_stats: Res<RunStats>,
+) {
+    let results = [
+        format!("Waves survived: {}", run_stats.waves_survived),
+        format!("Enemies killed: {}", run_stats.enemies_killed),
+        format!("Gadgets built: {}", run_stats.gadgets_built),
+        format!("Hit rate: {:.0}%", run_stats.hit_rate() * 100.0),
+    ];
+
+    commands
+        .spawn_bundle(overlay())
+        .insert(GameOverUi)
+        .with_children(|parent| {
+            parent.spawn_bundle(border()).with_children(|parent| {
+                parent
+                    .spawn_bundle(menu_background())
+                    .with_children(|parent| {
+                        parent.spawn_bundle(results_text(&asset_server, "Game Over", 90.0));
+                        for line in results.iter() {
+                            parent.spawn_bundle(results_text(&asset_server, line, 50.0));
+                        }
+
+                        for (label, game_over_button) in [
+                            ("Retry level", GameOverButton::Retry),
+                            ("Main menu", GameOverButton::MainMenu),
+                            ("Quit", GameOverButton::Quit),
+                        ] {
+                            parent
+                                .spawn_bundle(button())
+                                .with_children(|parent| {
+                                    parent.spawn_bundle(button_text(&asset_server, label));
+                                })
+                                .insert(game_over_button);
+                        }
+                    });
+            });
+        });
+}
+
+fn game_over_button_system(
+    buttons: GameOverButtonQuery,
+    mut state: ResMut<State<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        // the level gets respawned when entering the build phase
+        let result = match button {
+            GameOverButton::Retry => state.set(AppState::Build),
+            GameOverButton::MainMenu => state.set(AppState::MainMenu),
+            GameOverButton::Quit => {
+                exit.send(AppExit);
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            warn!("couldn't leave the game over screen: {:?}", err);
+        }
+    }
+}
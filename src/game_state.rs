@@ -1,10 +1,23 @@
-use bevy::prelude::*;
+use std::marker::PhantomData;
 
-use crate::{level::Base, enemy::Enemy};
+use bevy::{
+    ecs::{schedule::ShouldRun, system::SystemParam},
+    prelude::*,
+};
+
+use crate::{
+    enemy::{Enemy, EnemyKilled, EnemyType, Health, WaveConfig},
+    level::{Base, LevelComponent},
+    polishing_constants::STARTING_GOLD,
+    spatial_index::SpatialIndex,
+    WORLD_SIZE,
+};
 
 pub struct GameStatePlugin;
 
 const KILL_DIST: f32 = 170.0;
+/// How long a message to the player stays in the HUD.
+const HUD_MESSAGE_SECS: f32 = 3.0;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum AppState {
@@ -15,9 +28,132 @@ pub enum AppState {
     // Credits,
     Build,
     Attack,
+    /// Pushed on top of Build or Attack, which continue where they left off when popped.
+    Paused,
     GameOver,
 }
 
+impl AppState {
+    /// Whether the level is on hold: no physics, no spawning and no firing.
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, AppState::Paused | AppState::GameOver)
+    }
+}
+
+/// Run criterion for systems that must hold still while the level is frozen.
+pub fn unless_frozen(app_state: Res<State<AppState>>) -> ShouldRun {
+    if app_state.current().is_frozen() {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+/// Shows how many hit points the base has left.
+#[derive(Component)]
+pub struct BaseHealthText;
+
+/// Shows how much gold the player has.
+#[derive(Component)]
+pub struct GoldText;
+
+/// Shows the latest `HudMessage`.
+#[derive(Component)]
+pub struct MessageText;
+
+/// Feedback for the player, shown under the gold for a few seconds.
+pub struct HudMessage {
+    text: String,
+    timer: Timer,
+}
+
+impl Default for HudMessage {
+    fn default() -> Self {
+        HudMessage {
+            text: String::new(),
+            timer: Timer::from_seconds(HUD_MESSAGE_SECS, false),
+        }
+    }
+}
+
+impl HudMessage {
+    pub fn show(&mut self, text: String) {
+        info!("{}", text);
+        self.text = text;
+        self.timer.reset();
+    }
+}
+
+/// Gold to build gadgets with, earned from kills and surviving waves.
+#[derive(Debug)]
+pub struct Wallet {
+    pub balance: u32,
+}
+
+impl Default for Wallet {
+    fn default() -> Self {
+        Wallet {
+            balance: STARTING_GOLD,
+        }
+    }
+}
+
+impl Wallet {
+    /// Takes `cost` out of the wallet, unless there is not enough gold in it.
+    pub fn try_spend(&mut self, cost: u32) -> bool {
+        if self.balance < cost {
+            return false;
+        }
+        self.balance -= cost;
+        true
+    }
+}
+
+/// The wallet together with the HUD message that tells the player about it.
+#[derive(SystemParam)]
+pub struct Shop<'w, 's> {
+    pub wallet: ResMut<'w, Wallet>,
+    pub hud_message: ResMut<'w, HudMessage>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> Shop<'w, 's> {
+    /// Pays `cost` for `purchase`, or tells the player they can't afford it.
+    pub fn try_buy(&mut self, purchase: &str, cost: u32) -> bool {
+        if self.wallet.try_spend(cost) {
+            return true;
+        }
+        let balance = self.wallet.balance;
+        self.hud_message.show(format!(
+            "can't afford {} for {} gold, {} left",
+            purchase, cost, balance
+        ));
+        false
+    }
+}
+
+/// Results of the current run, shown on the game over screen.
+#[derive(Debug, Default)]
+pub struct RunStats {
+    pub waves_survived: u32,
+    pub enemies_killed: u32,
+    pub gadgets_built: u32,
+    pub shots_fired: u32,
+    /// Shots that hit an enemy, at most one per shot.
+    pub shots_hit: u32,
+}
+
+impl RunStats {
+    /// Share of the shots fired so far that hit an enemy.
+    pub fn hit_rate(&self) -> f32 {
+        if self.shots_fired == 0 {
+            return 0.0;
+        }
+        self.shots_hit as f32 / self.shots_fired as f32
+    }
+}
+
 pub struct WaveControler {
     pub wave_size: u32,
 
@@ -27,27 +163,166 @@ impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(AppState::Intro)
         .add_startup_system(audio_system)
+        .init_resource::<RunStats>()
+        .init_resource::<Wallet>()
+        .init_resource::<HudMessage>()
         .add_system(check_game_over)
+        .add_system(reward_kills)
+        .add_system(spawn_hud_text)
+        .add_system(update_hud_text.after(spawn_hud_text))
+        .add_system(update_hud_message.after(spawn_hud_text))
         .insert_resource(WaveControler{ wave_size: 1 });
     }
 }
 
 
-fn check_game_over(mut commands: Commands, base_query: Query<&Transform, With<Base>>, enemy_query: Query<&Transform, With<Enemy>>, mut app_state: ResMut<State<AppState>>, mut wave_controler: ResMut<WaveControler>) {
-    if base_query.is_empty() {
-        println!("no base in scene");
+/// Enemies that reach the base damage it and are used up, the run ends once it is destroyed.
+fn check_game_over(
+    mut commands: Commands,
+    mut base_query: Query<(&Transform, &mut Health), With<Base>>,
+    enemy_query: Query<&EnemyType, With<Enemy>>,
+    spatial_index: Res<SpatialIndex>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let (base_transform, mut base_health) = match base_query.get_single_mut() {
+        Ok(base) => base,
+        Err(_) => return,
+    };
+
+    let base_pos = base_transform.translation.truncate();
+    for (entity, _) in spatial_index.within_radius(base_pos, KILL_DIST) {
+        if let Ok(enemy_type) = enemy_query.get(entity) {
+            base_health.current -= enemy_type.stats().base_damage;
+            commands.entity(entity).despawn_recursive();
+            info!(
+                "base hit by {:?}, {} hp left",
+                enemy_type, base_health.current
+            );
+        }
+    }
+
+    if base_health.is_dead() && !app_state.current().is_frozen() {
+        println!("gameover");
+        // the run gets reset when leaving the game over screen
+        if let Err(err) = app_state.set(AppState::GameOver) {
+            warn!("couldn't switch state to GameOver: {:?}", err);
+        }
+    }
+}
+
+/// Throws away the level and all progress of the current run.
+/// The level gets respawned when entering the next build phase.
+pub fn reset_run(
+    commands: &mut Commands,
+    level_entities: impl Iterator<Item = Entity>,
+    wave_controler: &mut WaveControler,
+    run_stats: &mut RunStats,
+    wallet: &mut Wallet,
+) {
+    for entity in level_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    wave_controler.wave_size = 1;
+    *run_stats = RunStats::default();
+    *wallet = Wallet::default();
+    commands.insert_resource(WaveConfig::default());
+}
+
+fn reward_kills(
+    mut enemy_killed_events: EventReader<EnemyKilled>,
+    mut run_stats: ResMut<RunStats>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for enemy_killed in enemy_killed_events.iter() {
+        run_stats.enemies_killed += 1;
+        wallet.balance += enemy_killed.enemy_type.stats().reward;
+    }
+}
+
+fn spawn_hud_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    spawned_base_query: Query<(), Added<Base>>,
+) {
+    if spawned_base_query.is_empty() {
         return;
     }
-    let base_pos = base_query.single().translation;
-    for enemy_trans in enemy_query.iter() {
-        let enemy_pos = enemy_trans.translation;
-        let enemy_dist = base_pos.distance(enemy_pos);
-        dbg!(enemy_dist);
-        if enemy_dist < KILL_DIST && *app_state.current() != AppState::GameOver{
-            println!("gameover");
-            wave_controler.wave_size = 1;
-            app_state.set(AppState::Intro).unwrap();
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/Oswald-SemiBold.ttf"),
+        font_size: 100.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section("", text_style.clone())
+                .with_alignment(TextAlignment::TOP_LEFT),
+            transform: Transform::from_xyz(100.0, WORLD_SIZE.y - 50.0, 0.97),
+            ..default()
+        })
+        .insert(BaseHealthText)
+        .insert(LevelComponent);
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section("", text_style.clone())
+                .with_alignment(TextAlignment::TOP_LEFT),
+            transform: Transform::from_xyz(100.0, WORLD_SIZE.y - 170.0, 0.97),
+            ..default()
+        })
+        .insert(GoldText)
+        .insert(LevelComponent);
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 60.0,
+                    ..text_style
+                },
+            )
+            .with_alignment(TextAlignment::TOP_LEFT),
+            transform: Transform::from_xyz(100.0, WORLD_SIZE.y - 290.0, 0.97),
+            ..default()
+        })
+        .insert(MessageText)
+        .insert(LevelComponent);
+}
+
+fn update_hud_text(
+    base_query: Query<&Health, With<Base>>,
+    wallet: Res<Wallet>,
+    mut base_text_query: Query<&mut Text, (With<BaseHealthText>, Without<GoldText>)>,
+    mut gold_text_query: Query<&mut Text, (With<GoldText>, Without<BaseHealthText>)>,
+) {
+    if let Ok(health) = base_query.get_single() {
+        for mut text in base_text_query.iter_mut() {
+            text.sections[0].value =
+                format!("Base {:.0}/{:.0}", health.current.max(0.0), health.max);
+        }
+    }
+
+    for mut text in gold_text_query.iter_mut() {
+        text.sections[0].value = format!("Gold {}", wallet.balance);
+    }
+}
+
+fn update_hud_message(
+    time: Res<Time>,
+    mut hud_message: ResMut<HudMessage>,
+    mut message_text_query: Query<&mut Text, With<MessageText>>,
+) {
+    hud_message.timer.tick(time.delta());
+
+    for mut text in message_text_query.iter_mut() {
+        let section = &mut text.sections[0];
+        if section.value != hud_message.text {
+            section.value = hud_message.text.clone();
         }
+        section.style.color.set_a(1.0 - hud_message.timer.percent());
     }
 }
 
@@ -1,6 +1,9 @@
-use bevy::{prelude::*, render::camera::RenderTarget};
+use bevy::{ecs::system::SystemParam, prelude::*, render::camera::RenderTarget};
 
-use crate::{enemy::SpawnWaveEvent, gadget::SpawnCannonGadgetEvent, MainCamera};
+use crate::{
+    enemy::SpawnWaveEvent, gadget::SpawnGadgetEvent, gadget_catalog::SelectedGadget,
+    navmesh_debug::NavmeshDebug, MainCamera,
+};
 
 pub struct InputPlugin;
 
@@ -10,6 +13,19 @@ impl Plugin for InputPlugin {
     }
 }
 
+/// Where the cursor points at in the world, as a single system parameter.
+#[derive(SystemParam)]
+pub struct WorldCursor<'w, 's> {
+    windows: Res<'w, Windows>,
+    camera_q: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+}
+
+impl<'w, 's> WorldCursor<'w, 's> {
+    pub fn position(self) -> Option<Vec2> {
+        get_world_cursor_pos(self.windows, self.camera_q)
+    }
+}
+
 pub fn get_world_cursor_pos(
     windows: Res<Windows>,
     camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
@@ -50,14 +66,28 @@ fn debug_keymap(
     keys: Res<Input<KeyCode>>,
     mouse: Res<Input<MouseButton>>,
     mut spawn_wave_events: EventWriter<SpawnWaveEvent>,
-    mut spawn_cannon_events: EventWriter<SpawnCannonGadgetEvent>,
+    mut spawn_gadget_events: EventWriter<SpawnGadgetEvent>,
+    mut navmesh_debug: ResMut<NavmeshDebug>,
+    selected_gadget: Res<SelectedGadget>,
+    ui_buttons: Query<&Interaction, With<Button>>,
 ) {
     // Spawn next wave.
     if keys.just_pressed(KeyCode::N) {
         spawn_wave_events.send_default();
     }
 
-    if mouse.just_pressed(MouseButton::Left) {
-        spawn_cannon_events.send_default();
+    // Toggle the navmesh debug overlay.
+    if keys.just_pressed(KeyCode::F3) {
+        navmesh_debug.enabled = !navmesh_debug.enabled;
+    }
+
+    // clicks on the toolbar pick a gadget instead of placing one
+    let over_ui = ui_buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    if mouse.just_pressed(MouseButton::Left) && !over_ui {
+        spawn_gadget_events.send(SpawnGadgetEvent {
+            kind: selected_gadget.0,
+        });
     }
 }
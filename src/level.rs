@@ -1,12 +1,31 @@
-use bevy::{ecs::schedule::ShouldRun, prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{asset::LoadState, ecs::schedule::ShouldRun, prelude::*, sprite::MaterialMesh2dBundle};
 use bevy_rapier2d::prelude::*;
 
-use crate::{game_state::AppState, WORLD_SIZE};
+use crate::{
+    enemy::Health,
+    game_state::AppState,
+    level_asset::{ChunkDescription, LevelAsset, LevelAssetLoader},
+    polishing_constants::BASE_HEALTH,
+    wave_asset::{WaveScript, WaveScriptLoader},
+};
+
+/// Level loaded when entering the build phase for the first time.
+const DEFAULT_LEVEL: &str = "levels/cave.level.ron";
 
 pub struct LevelPlugin;
 
+/// Handle of the level file that gets spawned when entering the build phase.
+/// Replace it to switch to a different map.
+pub struct CurrentLevel(pub Handle<LevelAsset>);
+
+/// Handle of the wave file of the spawned level, if it has one.
+pub struct CurrentWaves(pub Handle<WaveScript>);
+
+/// A spawn point enemies come out of, named after its entry in the level file.
 #[derive(Debug, Default, Component)]
-pub struct Fountain;
+pub struct Fountain {
+    pub name: String,
+}
 
 #[derive(Debug, Default, Component)]
 pub struct Base;
@@ -16,11 +35,15 @@ pub struct LevelComponent;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_enter(AppState::Build)
-                .with_run_criteria(spawn_level_run_criterium)
-                .with_system(setup_map),
-        );
+        app.add_asset::<LevelAsset>()
+            .init_asset_loader::<LevelAssetLoader>()
+            .add_asset::<WaveScript>()
+            .init_asset_loader::<WaveScriptLoader>()
+            .add_startup_system(load_current_level);
+
+        // keeps checking until the level file is loaded and the level got spawned,
+        // the criterium only lets it run in Build or Attack while there is no level yet
+        app.add_system(setup_map.with_run_criteria(spawn_level_run_criterium));
 
         app.add_system_set(
             SystemSet::on_exit(AppState::Build)
@@ -70,14 +93,7 @@ fn cleanup_system<T: Component>(mut commands: Commands, q: Query<Entity, With<T>
 
 fn create_chunk(
     commands: &mut Commands,
-    width: f32,
-    height: f32,
-    position_x: f32,
-    position_y: f32,
-    rotation: f32,
-    meshes: &mut Assets<Mesh>,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    add_collider: bool,
+    chunk: &ChunkDescription,
     asset_server: &Res<AssetServer>,
 ) {
     let entity = commands
@@ -88,63 +104,92 @@ fn create_chunk(
                 ..default()
             },
             texture: asset_server.load("items/LowerTile.png"),
-            transform: Transform::from_scale(Vec3::new(width, height, 1.0))
-                .with_translation(Vec3::new(position_x, position_y, 0.1))
-                .with_rotation(Quat::from_rotation_z(rotation.to_radians())),
+            transform: Transform::from_scale(chunk.size.extend(1.0))
+                .with_translation(chunk.position.extend(0.1))
+                .with_rotation(Quat::from_rotation_z(chunk.rotation.to_radians())),
             ..default()
         })
+        .insert(chunk.kind)
+        .insert(chunk.clone())
         .insert(LevelComponent)
         .id();
 
-    if add_collider {
+    if chunk.kind.has_collider() {
         commands.entity(entity).insert(Collider::cuboid(1.0, 1.0));
     }
 }
 
+fn load_current_level(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CurrentLevel(asset_server.load(DEFAULT_LEVEL)));
+}
+
 fn setup_map(
     mut commands: Commands,
-    windows: Res<Windows>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    mut reported_failure: Local<bool>,
 ) {
-    let window = windows.get_primary().unwrap();
+    // the level file might still be loading, try again next frame
+    let level = match levels.get(&current_level.0) {
+        Some(level) => level,
+        None => {
+            if asset_server.get_load_state(&current_level.0) == LoadState::Failed
+                && !*reported_failure
+            {
+                error!("couldn't load the level file, there is nothing to play on");
+                *reported_failure = true;
+            }
+            return;
+        }
+    };
+    *reported_failure = false;
+
+    match &level.waves {
+        Some(waves) => commands.insert_resource(CurrentWaves(asset_server.load(waves))),
+        None => commands.remove_resource::<CurrentWaves>(),
+    }
 
-    let window_width = window.width() as f32;
-    let window_height = window.height() as f32;
     commands
         .spawn_bundle(SpriteBundle {
-            texture: asset_server.load("items/Background.png"),
+            texture: asset_server.load(&level.background.texture),
             sprite: Sprite {
                 custom_size: Some(Vec2::splat(2.0)),
                 ..default()
             },
-            transform: Transform::from_xyz(WORLD_SIZE.x / 2.0, WORLD_SIZE.y / 2.0 + 1000.0, 0.05)
-                .with_scale(Vec3::new(WORLD_SIZE.x, WORLD_SIZE.y, 1.0)),
+            transform: Transform::from_translation(level.background.position.extend(0.05))
+                .with_scale(level.background.size.extend(1.0)),
             ..default()
         })
         .insert(LevelComponent);
 
-    let fountain_offset = Transform::from_xyz(180.0, 135.0, 1.0);
-    commands
-        .spawn()
-        .insert(Fountain)
-        .insert(LevelComponent)
-        .insert_bundle(MaterialMesh2dBundle {
-            mesh: meshes
-                .add(Mesh::from(shape::Quad::new(Vec2::new(200.0, 200.0))))
-                .into(),
-            material: materials.add(ColorMaterial::from(Color::RED)),
-            transform: fountain_offset,
-            visibility: Visibility { is_visible: false },
-            ..default()
-        });
+    for spawn_point in level.spawn_points.iter() {
+        let fountain_offset = Transform::from_translation(spawn_point.position.extend(1.0));
+        commands
+            .spawn()
+            .insert(Fountain {
+                name: spawn_point.name.clone(),
+            })
+            .insert(LevelComponent)
+            .insert_bundle(MaterialMesh2dBundle {
+                mesh: meshes
+                    .add(Mesh::from(shape::Quad::new(Vec2::new(200.0, 200.0))))
+                    .into(),
+                material: materials.add(ColorMaterial::from(Color::RED)),
+                transform: fountain_offset,
+                visibility: Visibility { is_visible: false },
+                ..default()
+            });
+    }
 
-    let base_offset = Transform::from_xyz(1700.0, 1885.0, 0.06);
+    let base_offset = Transform::from_translation(level.base.extend(0.06));
     commands
         .spawn()
         .insert(LevelComponent)
         .insert(Base)
+        .insert(Health::new(BASE_HEALTH))
         .insert_bundle(SpriteBundle {
             texture: asset_server.load("items/Castle.png"),
             sprite: Sprite {
@@ -155,635 +200,9 @@ fn setup_map(
             ..default()
         });
 
-    // Colliders around the map to prevent everything from leaving the map
-    // bottom
-    create_chunk(
-        &mut commands,
-        WORLD_SIZE.x + 200.0,
-        100.0,
-        0.0,
-        -100.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    //top
-    create_chunk(
-        &mut commands,
-        WORLD_SIZE.x + 200.0,
-        100.0,
-        0.0,
-        WORLD_SIZE.y + 100.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    //left
-    create_chunk(
-        &mut commands,
-        WORLD_SIZE.y,
-        100.0,
-        -200.0,
-        0.0,
-        90.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    //right
-    create_chunk(
-        &mut commands,
-        WORLD_SIZE.y,
-        100.0,
-        WORLD_SIZE.x + 200.0,
-        0.0,
-        90.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-
-    // ground
-    create_chunk(
-        &mut commands,
-        1530.0,
-        35.0,
-        1600.0,
-        50.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-
-    // cave exit enemies left and right
-
-    create_chunk(
-        &mut commands,
-        35.0,
-        150.0,
-        35.0,
-        120.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        35.0,
-        150.0,
-        3165.0,
-        120.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-
-    // chunks for walking; not rotated; from lowest to highest
-
-    create_chunk(
-        &mut commands,
-        150.0,
-        35.0,
-        2045.0,
-        500.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        325.0,
-        35.0,
-        325.0,
-        700.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        200.0,
-        35.0,
-        2900.0,
-        750.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        200.0,
-        35.0,
-        1386.0,
-        1012.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        420.0,
-        35.0,
-        750.0,
-        1304.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        400.0,
-        35.0,
-        1600.0,
-        1700.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-
-    // chunks for walking up, from lowest to highest
-    create_chunk(
-        &mut commands,
-        400.0,
-        35.0,
-        2500.0,
-        280.0,
-        145.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        250.0,
-        35.0,
-        830.0,
-        570.0,
-        145.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        320.0,
-        35.0,
-        1750.0,
-        770.0,
-        127.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        125.0,
-        35.0,
-        273.0,
-        1222.0,
-        50.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        400.0,
-        35.0,
-        2300.0,
-        1480.0,
-        145.0,
-        &mut meshes,
-        &mut materials,
-        true,
-        &asset_server,
-    );
-
-    // ladder priority one: from right to to left, priority second: from lowest to highest
-
-    // first
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1150.0,
-        140.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1150.0,
-        215.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1150.0,
-        290.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1150.0,
-        365.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1150.0,
-        435.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-
-    //first
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        100.0,
-        775.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        100.0,
-        850.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        100.0,
-        925.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        100.0,
-        1000.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        100.0,
-        1075.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-
-    //last
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1300.0,
-        1075.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1300.0,
-        1150.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1300.0,
-        1225.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1300.0,
-        1300.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-
-    //last
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1100.0,
-        1400.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1100.0,
-        1475.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1100.0,
-        1550.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1100.0,
-        1625.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        1100.0,
-        1700.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-
-    //vorletzter
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        2750.0,
-        840.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        2750.0,
-        915.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        2750.0,
-        990.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        2750.0,
-        1065.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        2750.0,
-        1140.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-
-    //last
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        140.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        215.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        290.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        365.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        435.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        510.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        585.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
-    create_chunk(
-        &mut commands,
-        50.0,
-        17.5,
-        3000.0,
-        660.0,
-        0.0,
-        &mut meshes,
-        &mut materials,
-        false,
-        &asset_server,
-    );
+    for chunk in level.all_chunks() {
+        create_chunk(&mut commands, &chunk, &asset_server);
+    }
 }
 
 #[warn(dead_code)]
@@ -791,7 +210,7 @@ fn spawn_fountain(mut commands: Commands) {
     let fountain_offset = Transform::from_xyz(100.0, 100.0, 0.0);
     commands
         .spawn()
-        .insert(Fountain)
+        .insert(Fountain::default())
         .insert_bundle(TransformBundle::from(fountain_offset));
 
     let base_offset = Transform::from_xyz(1500.0, 500.0, 0.0);
@@ -0,0 +1,166 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+/// Vertical distance between two rungs of a ladder.
+pub const LADDER_RUNG_SPACING: f32 = 75.0;
+
+/// Half extents of a single ladder rung.
+const LADDER_RUNG_SIZE: Vec2 = Vec2::new(50.0, 17.5);
+
+/// A level layout as authored in `assets/levels/*.level.ron`.
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "5a3c1e4f-8d2b-4b7a-9f61-0c2e7d9b1a34"]
+pub struct LevelAsset {
+    pub background: BackgroundDescription,
+    /// Where enemies come from, the first one is the default.
+    pub spawn_points: Vec<SpawnPointDescription>,
+    pub base: Vec2,
+    pub chunks: Vec<ChunkDescription>,
+    #[serde(default)]
+    pub ladders: Vec<LadderDescription>,
+    #[serde(default)]
+    pub pathfinding: PathfindingMode,
+    /// Path of the wave file, e.g. `Some("levels/cave.waves.ron")`. Without one
+    /// waves just grow by one enemy per type each time.
+    #[serde(default)]
+    pub waves: Option<String>,
+}
+
+/// How enemies find their way to the base.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum PathfindingMode {
+    /// Every enemy plans and follows its own route through the navmesh.
+    #[default]
+    Navmesh,
+    /// All enemies share one precomputed flow field towards the base.
+    FlowField,
+}
+
+/// A named spot enemies spawn at, e.g. a cave exit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpawnPointDescription {
+    pub name: String,
+    pub position: Vec2,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackgroundDescription {
+    pub texture: String,
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Component)]
+pub enum ChunkKind {
+    /// Invisible walls around the map that keep everything inside.
+    Boundary,
+    /// Solid walls, e.g. the cave exits.
+    Wall,
+    /// Flat chunks enemies walk on.
+    Walkable,
+    /// Rotated chunks enemies walk up.
+    Slope,
+    /// Ladder rungs, without a collider.
+    Ladder,
+}
+
+impl ChunkKind {
+    pub fn has_collider(&self) -> bool {
+        *self != ChunkKind::Ladder
+    }
+}
+
+/// A single rectangular chunk; `size` holds half extents and `rotation` is in degrees.
+/// Spawned chunks keep their description as a component.
+#[derive(Component, Debug, Clone, Deserialize)]
+pub struct ChunkDescription {
+    pub kind: ChunkKind,
+    pub size: Vec2,
+    pub position: Vec2,
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl ChunkDescription {
+    /// The upward facing side of the chunk, ordered from left to right.
+    pub fn top_surface(&self) -> (Vec2, Vec2) {
+        let rotation = self.rotation.to_radians();
+        let direction = Vec2::new(rotation.cos(), rotation.sin());
+        let mut normal = direction.perp();
+        if normal.y < 0.0 {
+            normal = -normal;
+        }
+
+        let center = self.position + normal * self.size.y;
+        let a = center - direction * self.size.x;
+        let b = center + direction * self.size.x;
+        if a.x <= b.x {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+}
+
+/// A ladder column at `x`, with rungs spread evenly from `bottom` to `top`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LadderDescription {
+    pub x: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl LadderDescription {
+    pub fn rungs(&self) -> Vec<ChunkDescription> {
+        let steps = ((self.top - self.bottom) / LADDER_RUNG_SPACING)
+            .round()
+            .max(1.0) as usize;
+
+        (0..=steps)
+            .map(|step| {
+                let t = step as f32 / steps as f32;
+                ChunkDescription {
+                    kind: ChunkKind::Ladder,
+                    size: LADDER_RUNG_SIZE,
+                    position: Vec2::new(self.x, self.bottom + (self.top - self.bottom) * t),
+                    rotation: 0.0,
+                }
+            })
+            .collect()
+    }
+}
+
+impl LevelAsset {
+    /// All chunks of the level, including the generated ladder rungs.
+    pub fn all_chunks(&self) -> impl Iterator<Item = ChunkDescription> + '_ {
+        self.chunks
+            .iter()
+            .cloned()
+            .chain(self.ladders.iter().flat_map(|ladder| ladder.rungs()))
+    }
+}
+
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+impl AssetLoader for LevelAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<LevelAsset>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
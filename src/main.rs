@@ -5,7 +5,9 @@ use bevy::{math::vec2, prelude::*};
 use bevy_easings::EasingsPlugin;
 use bevy_rapier2d::prelude::*;
 use build_state::BuildStatePlugin;
+use droplet_pool::DropletPoolPlugin;
 use enemy::{EnemyPlugin, SpawnWaveEvent};
+use game_over::GameOverPlugin;
 use game_state::{AppState, GameStatePlugin};
 use menu::MainMenuPlugin;
 
@@ -14,19 +16,35 @@ use input::InputPlugin as GameInputPlugin;
 
 use level::LevelPlugin;
 use pathfinding::PathfindingPlugin;
+use pause::PausePlugin;
+use spatial_index::SpatialIndexPlugin;
+use toolbar::ToolbarPlugin;
 use polishing_constants::PIXELS_PER_METER_POLISHING;
 
 mod attack_state;
 mod attack_system;
+mod ballistics;
 mod build_state;
+mod droplet_pool;
 mod enemy;
+mod flow_field;
 mod gadget;
+mod gadget_catalog;
+mod game_over;
 mod game_state;
 mod input;
 mod level;
+mod level_asset;
 mod menu;
+mod navmesh;
+mod navmesh_debug;
 mod pathfinding;
+mod pause;
 mod polishing_constants;
+mod spatial_index;
+mod targeting;
+mod toolbar;
+mod wave_asset;
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Component)]
 pub struct MainCamera;
 
@@ -41,7 +59,9 @@ fn main() {
         .add_plugin(PathfindingPlugin)
         .add_plugin(LevelPlugin)
         .add_plugin(GadgetPlugin)
+        .add_plugin(DropletPoolPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(SpatialIndexPlugin)
         .add_plugin(GameInputPlugin)
         .add_plugin(EasingsPlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
@@ -49,6 +69,9 @@ fn main() {
         ))
         // .add_plugin(RapierDebugRenderPlugin::default())
         .add_plugin(GameStatePlugin)
+        .add_plugin(GameOverPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(ToolbarPlugin)
         .add_plugin(AudioPlugin)
         .add_plugin(MainMenuPlugin)
         .add_plugin(BuildStatePlugin)
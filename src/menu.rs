@@ -79,7 +79,21 @@ fn root() -> NodeBundle {
     }
 }
 
-fn border() -> NodeBundle {
+/// Full screen, see-through backdrop for menus shown on top of the level.
+pub fn overlay() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        color: UiColor(Color::hex("0C1E21CC").unwrap()),
+        ..Default::default()
+    }
+}
+
+pub fn border() -> NodeBundle {
     NodeBundle {
         style: Style {
             size: Size::new(Val::Px(800.0), Val::Auto),
@@ -91,7 +105,7 @@ fn border() -> NodeBundle {
     }
 }
 
-fn menu_background() -> NodeBundle {
+pub fn menu_background() -> NodeBundle {
     NodeBundle {
         style: Style {
             size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -105,7 +119,7 @@ fn menu_background() -> NodeBundle {
     }
 }
 
-fn button() -> ButtonBundle {
+pub fn button() -> ButtonBundle {
     ButtonBundle {
         style: Style {
             size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -137,7 +151,7 @@ fn intro_text(asset_server: &Res<AssetServer>) -> TextBundle {
     };
 }
 
-fn button_text(asset_server: &Res<AssetServer>, label: &str) -> TextBundle {
+pub fn button_text(asset_server: &Res<AssetServer>, label: &str) -> TextBundle {
     return TextBundle {
         style: Style {
             margin: UiRect::all(Val::Px(10.0)),
@@ -230,17 +244,37 @@ fn despawn_main_menu(mut commands: Commands, menu_data: Res<MenuData>) {
 }
 
 fn main_menu_controls(mut keys: ResMut<Input<KeyCode>>, mut app_state: ResMut<State<AppState>>) {
-    if *app_state.current() == AppState::MainMenu {
-        if keys.just_pressed(KeyCode::Return) {
-            app_state.set(AppState::Build).unwrap();
-            keys.reset(KeyCode::Return);
+    match app_state.current() {
+        AppState::MainMenu => {
+            if keys.just_pressed(KeyCode::Return) {
+                app_state.set(AppState::Build).unwrap();
+                keys.reset(KeyCode::Return);
+            }
+        }
+        // pausing keeps the level and the phase timers around
+        AppState::Build | AppState::Attack => {
+            if keys.just_pressed(KeyCode::Escape) {
+                // fails if the phase is already about to change this frame
+                if let Err(err) = app_state.push(AppState::Paused) {
+                    warn!("couldn't pause: {:?}", err);
+                }
+                keys.reset(KeyCode::Escape);
+            }
         }
-    } else {
-        // todo remember build timer so it is not resetted when going to menu
-        if keys.just_pressed(KeyCode::Escape) {
-            // lololol
-            app_state.set(AppState::Intro).unwrap();
-            keys.reset(KeyCode::Escape);
+        AppState::Paused => {
+            if keys.just_pressed(KeyCode::Escape) {
+                if let Err(err) = app_state.pop() {
+                    warn!("couldn't resume: {:?}", err);
+                }
+                keys.reset(KeyCode::Escape);
+            }
+        }
+        _ => {
+            if keys.just_pressed(KeyCode::Escape) {
+                // lololol
+                app_state.set(AppState::Intro).unwrap();
+                keys.reset(KeyCode::Escape);
+            }
         }
     }
 }
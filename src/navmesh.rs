@@ -0,0 +1,471 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+use crate::level_asset::{ChunkKind, LevelAsset};
+
+/// Height of a walking agent's center above the surface it walks on.
+const WALK_HEIGHT: f32 = 60.0;
+/// How far the end of a surface may be from the surface it leads onto.
+const SURFACE_LINK_REACH: f32 = 100.0;
+const LADDER_BOTTOM_REACH: f32 = 100.0;
+/// Ladders end a bit below the surface they lead onto, so their top needs a longer reach.
+const LADDER_TOP_REACH: f32 = 220.0;
+/// How far the base and the spawn points may be from the closest surface.
+const LANDMARK_REACH: f32 = 300.0;
+/// How far past the end of a surface an agent lands when dropping off it.
+const DROP_OVERHANG: f32 = 40.0;
+/// Horizontal spacing of the spots where diggers can burrow up to the surface above.
+const DIG_SPACING: f32 = 300.0;
+/// How far up diggers can burrow.
+const DIG_REACH: f32 = 500.0;
+const NODE_DENSITY: f32 = 0.1;
+/// How many of the closest nodes are checked when looking for a reachable one.
+const REACHABLE_CANDIDATES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Walk,
+    Climb,
+    Drop,
+    /// Straight up through solid ground, only taken by diggers.
+    Dig,
+}
+
+impl EdgeKind {
+    /// Cost per unit of distance. Never below 1.0, so the straight line distance
+    /// stays a valid A* heuristic.
+    fn cost_factor(&self) -> f32 {
+        match self {
+            EdgeKind::Walk => 1.0,
+            EdgeKind::Climb => 2.0,
+            EdgeKind::Drop => 1.0,
+            EdgeKind::Dig => 1.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NavEdge {
+    pub to: usize,
+    pub kind: EdgeKind,
+    pub cost: f32,
+}
+
+/// A point along a path, with the kind of edge that leads to it.
+#[derive(Debug, Clone, Copy)]
+pub struct Waypoint {
+    pub position: Vec2,
+    pub kind: EdgeKind,
+}
+
+/// Navigation graph of a level; every path ends at the base.
+#[derive(Default)]
+pub struct Navmesh {
+    pub nodes: Vec<Vec2>,
+    pub edges: Vec<Vec<NavEdge>>,
+    goal: Option<usize>,
+}
+
+impl Navmesh {
+    /// Builds the navigation graph out of the level geometry.
+    pub fn from_level(level: &LevelAsset) -> Self {
+        let mut navmesh = Navmesh::default();
+        let mut surfaces = Vec::new();
+
+        for chunk in level
+            .chunks
+            .iter()
+            .filter(|chunk| matches!(chunk.kind, ChunkKind::Walkable | ChunkKind::Slope))
+        {
+            let (left, right) = chunk.top_surface();
+            let offset = (right - left).perp().normalize() * WALK_HEIGHT;
+            let nodes = vec![
+                navmesh.add_node(left + offset),
+                navmesh.add_node(right + offset),
+            ];
+            surfaces.push(Surface {
+                left: left + offset,
+                right: right + offset,
+                nodes,
+            });
+        }
+
+        // the ends of a surface lead onto neighbouring surfaces, or drop down onto the one below
+        for surface_idx in 0..surfaces.len() {
+            for (end, outward) in [(0, -1.0), (1, 1.0)] {
+                let node = surfaces[surface_idx].nodes[end];
+                navmesh.attach(
+                    &mut surfaces,
+                    node,
+                    SURFACE_LINK_REACH,
+                    Some(surface_idx),
+                    |_| true,
+                );
+                navmesh.add_drop(&mut surfaces, node, surface_idx, outward);
+            }
+        }
+
+        for ladder in level.ladders.iter() {
+            let bottom = navmesh.add_node(Vec2::new(ladder.x, ladder.bottom));
+            let top = navmesh.add_node(Vec2::new(ladder.x, ladder.top));
+            navmesh.connect(bottom, top, EdgeKind::Climb);
+            navmesh.attach(&mut surfaces, bottom, LADDER_BOTTOM_REACH, None, |_| true);
+            navmesh.attach(&mut surfaces, top, LADDER_TOP_REACH, None, |target| {
+                target.y >= ladder.top
+            });
+        }
+
+        // diggers burrow from a surface straight up onto the one above
+        for surface_idx in 0..surfaces.len() {
+            let (left, right) = (surfaces[surface_idx].left, surfaces[surface_idx].right);
+            let mut x = left.x + DIG_SPACING / 2.0;
+            while x < right.x {
+                navmesh.add_dig(&mut surfaces, surface_idx, x);
+                x += DIG_SPACING;
+            }
+        }
+
+        let goal = navmesh.add_node(level.base);
+        navmesh.attach(&mut surfaces, goal, LANDMARK_REACH, None, |_| true);
+        navmesh.goal = Some(goal);
+
+        for spawn_point in level.spawn_points.iter() {
+            let node = navmesh.add_node(spawn_point.position);
+            navmesh.attach(&mut surfaces, node, LANDMARK_REACH, None, |_| true);
+        }
+
+        for surface in surfaces.iter() {
+            let mut nodes = surface.nodes.clone();
+            nodes.sort_by(|a, b| navmesh.nodes[*a].x.total_cmp(&navmesh.nodes[*b].x));
+
+            for pair in nodes.windows(2) {
+                navmesh.connect(pair[0], pair[1], EdgeKind::Walk);
+            }
+        }
+
+        navmesh
+    }
+
+    /// The closest of the nearby nodes that passes `is_reachable`, or simply the closest node
+    /// if none of them does.
+    pub fn closest_reachable_node(
+        &self,
+        position: Vec2,
+        is_reachable: impl Fn(Vec2) -> bool,
+    ) -> Option<usize> {
+        let mut candidates: Vec<usize> = (0..self.nodes.len()).collect();
+        candidates.sort_by(|a, b| {
+            self.nodes[*a]
+                .distance_squared(position)
+                .total_cmp(&self.nodes[*b].distance_squared(position))
+        });
+
+        candidates
+            .iter()
+            .take(REACHABLE_CANDIDATES)
+            .find(|idx| is_reachable(self.nodes[**idx]))
+            .or_else(|| candidates.first())
+            .copied()
+    }
+
+    /// A* from the closest reachable node to the base, through dig edges only if `can_dig`.
+    /// `cost_noise` may add extra cost to an edge, which lets agents spread over
+    /// alternative routes. Returns the waypoints to follow, spaced out for steering.
+    pub fn find_path(
+        &self,
+        from: Vec2,
+        is_reachable: impl Fn(Vec2) -> bool,
+        can_dig: bool,
+        cost_noise: impl Fn(usize, usize) -> f32,
+    ) -> Option<Vec<Waypoint>> {
+        let start = self.closest_reachable_node(from, is_reachable)?;
+        let goal = self.goal?;
+        let goal_pos = self.nodes[goal];
+
+        let mut costs = vec![f32::INFINITY; self.nodes.len()];
+        let mut previous = vec![None; self.nodes.len()];
+        let mut open = BinaryHeap::new();
+        costs[start] = 0.0;
+        open.push(OpenNode {
+            estimate: self.nodes[start].distance(goal_pos),
+            node: start,
+        });
+
+        while let Some(OpenNode { node: current, .. }) = open.pop() {
+            if current == goal {
+                return Some(self.waypoints(&previous, goal));
+            }
+
+            for edge in self.edges[current].iter() {
+                if edge.kind == EdgeKind::Dig && !can_dig {
+                    continue;
+                }
+
+                let cost = costs[current] + edge.cost * (1.0 + cost_noise(current, edge.to));
+                if cost < costs[edge.to] {
+                    costs[edge.to] = cost;
+                    previous[edge.to] = Some((current, edge.kind));
+                    open.push(OpenNode {
+                        estimate: cost + self.nodes[edge.to].distance(goal_pos),
+                        node: edge.to,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Cost of the cheapest route from every node to the base without digging,
+    /// infinite where there is none.
+    pub fn costs_to_goal(&self) -> Vec<f32> {
+        let mut costs = vec![f32::INFINITY; self.nodes.len()];
+        let goal = match self.goal {
+            Some(goal) => goal,
+            None => return costs,
+        };
+
+        let mut incoming = vec![Vec::new(); self.nodes.len()];
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges.iter().filter(|edge| edge.kind != EdgeKind::Dig) {
+                incoming[edge.to].push((from, edge.cost));
+            }
+        }
+
+        let mut open = BinaryHeap::new();
+        costs[goal] = 0.0;
+        open.push(OpenNode {
+            estimate: 0.0,
+            node: goal,
+        });
+
+        while let Some(OpenNode {
+            estimate,
+            node: current,
+        }) = open.pop()
+        {
+            if estimate > costs[current] {
+                continue;
+            }
+
+            for &(from, edge_cost) in incoming[current].iter() {
+                let cost = costs[current] + edge_cost;
+                if cost < costs[from] {
+                    costs[from] = cost;
+                    open.push(OpenNode {
+                        estimate: cost,
+                        node: from,
+                    });
+                }
+            }
+        }
+
+        costs
+    }
+
+    fn waypoints(&self, previous: &[Option<(usize, EdgeKind)>], goal: usize) -> Vec<Waypoint> {
+        let mut path = Vec::new();
+        let mut current = goal;
+        while let Some((prev, kind)) = previous[current] {
+            path.push(Waypoint {
+                position: self.nodes[current],
+                kind,
+            });
+            current = prev;
+        }
+        path.push(Waypoint {
+            position: self.nodes[current],
+            kind: EdgeKind::Walk,
+        });
+        path.reverse();
+
+        densify(path, NODE_DENSITY)
+    }
+
+    fn add_node(&mut self, position: Vec2) -> usize {
+        self.nodes.push(position);
+        self.edges.push(Vec::new());
+        self.nodes.len() - 1
+    }
+
+    fn connect(&mut self, a: usize, b: usize, kind: EdgeKind) {
+        self.connect_one_way(a, b, kind);
+        self.connect_one_way(b, a, kind);
+    }
+
+    fn connect_one_way(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        if from == to || self.edges[from].iter().any(|edge| edge.to == to) {
+            return;
+        }
+        let cost = self.nodes[from].distance(self.nodes[to]) * kind.cost_factor();
+        self.edges[from].push(NavEdge { to, kind, cost });
+    }
+
+    /// Connects `node` to the closest point within `reach` on any surface except `skip_surface`.
+    /// Points in the middle of a surface become new nodes on that surface.
+    fn attach(
+        &mut self,
+        surfaces: &mut [Surface],
+        node: usize,
+        reach: f32,
+        skip_surface: Option<usize>,
+        accept: impl Fn(Vec2) -> bool,
+    ) {
+        let position = self.nodes[node];
+        // (distance, surface, target point, existing node at that point)
+        let mut closest: Option<(f32, usize, Vec2, Option<usize>)> = None;
+
+        for (surface_idx, surface) in surfaces.iter().enumerate() {
+            if skip_surface == Some(surface_idx) {
+                continue;
+            }
+
+            let existing = surface
+                .nodes
+                .iter()
+                .map(|&idx| (self.nodes[idx], Some(idx)));
+            let projected = surface.project(position.x).map(|point| (point, None));
+
+            for (target, target_node) in existing.chain(projected) {
+                let dist = position.distance(target);
+                if target_node == Some(node)
+                    || dist > reach
+                    || !accept(target)
+                    || closest.is_some_and(|(closest_dist, ..)| dist >= closest_dist)
+                {
+                    continue;
+                }
+                closest = Some((dist, surface_idx, target, target_node));
+            }
+        }
+
+        if let Some((_, surface_idx, target, target_node)) = closest {
+            let target_node =
+                target_node.unwrap_or_else(|| self.add_surface_node(surfaces, surface_idx, target));
+            self.connect(node, target_node, EdgeKind::Walk);
+        }
+    }
+
+    /// Lets agents walk off the end of a surface onto the highest surface below it.
+    fn add_drop(
+        &mut self,
+        surfaces: &mut [Surface],
+        node: usize,
+        surface_idx: usize,
+        outward: f32,
+    ) {
+        let position = self.nodes[node];
+        let landing_x = position.x + outward * DROP_OVERHANG;
+
+        let landing = surfaces
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != surface_idx)
+            .filter_map(|(idx, surface)| surface.project(landing_x).map(|point| (idx, point)))
+            .filter(|(_, point)| point.y < position.y)
+            .max_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+
+        if let Some((landing_idx, point)) = landing {
+            let landing_node = self.add_surface_node(surfaces, landing_idx, point);
+            self.connect_one_way(node, landing_node, EdgeKind::Drop);
+        }
+    }
+
+    /// Lets diggers burrow from `x` on a surface up onto the closest surface above it.
+    fn add_dig(&mut self, surfaces: &mut [Surface], surface_idx: usize, x: f32) {
+        let start = match surfaces[surface_idx].project(x) {
+            Some(start) => start,
+            None => return,
+        };
+
+        let exit = surfaces
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != surface_idx)
+            .filter_map(|(idx, surface)| surface.project(x).map(|point| (idx, point)))
+            .filter(|(_, point)| point.y > start.y && point.y - start.y <= DIG_REACH)
+            .min_by(|(_, a), (_, b)| a.y.total_cmp(&b.y));
+
+        if let Some((exit_idx, exit)) = exit {
+            let start_node = self.add_surface_node(surfaces, surface_idx, start);
+            let exit_node = self.add_surface_node(surfaces, exit_idx, exit);
+            self.connect_one_way(start_node, exit_node, EdgeKind::Dig);
+        }
+    }
+
+    fn add_surface_node(
+        &mut self,
+        surfaces: &mut [Surface],
+        surface_idx: usize,
+        position: Vec2,
+    ) -> usize {
+        let node = self.add_node(position);
+        surfaces[surface_idx].nodes.push(node);
+        node
+    }
+}
+
+/// A line agents walk along, with the graph nodes lying on it.
+struct Surface {
+    left: Vec2,
+    right: Vec2,
+    nodes: Vec<usize>,
+}
+
+impl Surface {
+    fn project(&self, x: f32) -> Option<Vec2> {
+        if x < self.left.x || x > self.right.x || self.left.x == self.right.x {
+            return None;
+        }
+
+        let t = (x - self.left.x) / (self.right.x - self.left.x);
+        Some(self.left.lerp(self.right, t))
+    }
+}
+
+/// Entry of the A* open set, ordered so the heap pops the lowest estimate first.
+#[derive(PartialEq)]
+struct OpenNode {
+    estimate: f32,
+    node: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Inserts extra waypoints so consecutive ones are at most `1 / density_npu` apart.
+fn densify(preset_nodes: Vec<Waypoint>, density_npu: f32) -> Vec<Waypoint> {
+    let mut nodes = vec![preset_nodes[0]];
+
+    for i in 1..preset_nodes.len() {
+        let last_node = preset_nodes[i - 1];
+        let next_node = preset_nodes[i];
+
+        let dist = last_node.position.distance(next_node.position);
+
+        let steps = (dist * density_npu) as usize;
+
+        for step_nr in 1..steps {
+            let t = step_nr as f32 / steps as f32;
+            nodes.push(Waypoint {
+                position: last_node.position.lerp(next_node.position, t),
+                kind: next_node.kind,
+            });
+        }
+        nodes.push(next_node);
+    }
+
+    nodes
+}
@@ -0,0 +1,171 @@
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+use bevy_rapier2d::prelude::ExternalForce;
+
+use crate::{
+    navmesh::{EdgeKind, Navmesh},
+    pathfinding::PathfindingAgent,
+};
+
+const DEBUG_Z: f32 = 1.9;
+const LINE_WIDTH: f32 = 4.0;
+const NODE_SIZE: f32 = 10.0;
+/// Length in pixels a unit of steering force is drawn with.
+const FORCE_DRAW_SCALE: f32 = 0.2;
+
+/// Whether the navmesh debug overlay is shown, toggled with F3.
+#[derive(Debug, Default)]
+pub struct NavmeshDebug {
+    pub enabled: bool,
+}
+
+/// Part of the overlay that shows the navmesh itself.
+#[derive(Component)]
+pub struct NavmeshDebugShape;
+
+/// Part of the overlay that shows what an agent is doing this frame.
+#[derive(Component)]
+pub struct AgentDebugShape;
+
+/// Shared mesh and materials, so the overlay does not add new assets every frame.
+pub struct NavmeshDebugAssets {
+    quad: Mesh2dHandle,
+    node: Handle<ColorMaterial>,
+    walk: Handle<ColorMaterial>,
+    climb: Handle<ColorMaterial>,
+    drop: Handle<ColorMaterial>,
+    dig: Handle<ColorMaterial>,
+    target: Handle<ColorMaterial>,
+    force: Handle<ColorMaterial>,
+}
+
+impl FromWorld for NavmeshDebugAssets {
+    fn from_world(world: &mut World) -> Self {
+        let quad = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(shape::Quad::new(Vec2::ONE).into())
+            .into();
+
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        let mut material = |color: Color| materials.add(ColorMaterial::from(color));
+
+        NavmeshDebugAssets {
+            quad,
+            node: material(Color::PURPLE),
+            walk: material(Color::GREEN),
+            climb: material(Color::YELLOW),
+            drop: material(Color::ORANGE_RED),
+            dig: material(Color::MAROON),
+            target: material(Color::CYAN),
+            force: material(Color::WHITE),
+        }
+    }
+}
+
+impl NavmeshDebugAssets {
+    fn line(&self, from: Vec2, to: Vec2, material: Handle<ColorMaterial>) -> ColorMesh2dBundle {
+        let delta = to - from;
+        ColorMesh2dBundle {
+            mesh: self.quad.clone(),
+            material,
+            transform: Transform::from_translation(((from + to) / 2.0).extend(DEBUG_Z))
+                .with_rotation(Quat::from_rotation_z(delta.y.atan2(delta.x)))
+                .with_scale(Vec3::new(delta.length(), LINE_WIDTH, 1.0)),
+            ..default()
+        }
+    }
+
+    fn point(&self, position: Vec2, material: Handle<ColorMaterial>) -> ColorMesh2dBundle {
+        ColorMesh2dBundle {
+            mesh: self.quad.clone(),
+            material,
+            transform: Transform::from_translation(position.extend(DEBUG_Z))
+                .with_scale(Vec3::new(NODE_SIZE, NODE_SIZE, 1.0)),
+            ..default()
+        }
+    }
+}
+
+/// Redraws the navmesh when the overlay gets toggled or the navmesh changes.
+pub fn sync_navmesh_debug(
+    mut commands: Commands,
+    navmesh_debug: Res<NavmeshDebug>,
+    navmesh: Res<Navmesh>,
+    debug_assets: Res<NavmeshDebugAssets>,
+    shape_query: Query<Entity, With<NavmeshDebugShape>>,
+) {
+    if !navmesh_debug.is_changed() && !navmesh.is_changed() {
+        return;
+    }
+
+    for entity in shape_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !navmesh_debug.enabled {
+        return;
+    }
+
+    for (from, edges) in navmesh.edges.iter().enumerate() {
+        for edge in edges.iter() {
+            let material = match edge.kind {
+                EdgeKind::Walk => debug_assets.walk.clone(),
+                EdgeKind::Climb => debug_assets.climb.clone(),
+                EdgeKind::Drop => debug_assets.drop.clone(),
+                EdgeKind::Dig => debug_assets.dig.clone(),
+            };
+            commands
+                .spawn_bundle(debug_assets.line(
+                    navmesh.nodes[from],
+                    navmesh.nodes[edge.to],
+                    material,
+                ))
+                .insert(NavmeshDebugShape);
+        }
+    }
+
+    for node in navmesh.nodes.iter() {
+        commands
+            .spawn_bundle(debug_assets.point(*node, debug_assets.node.clone()))
+            .insert(NavmeshDebugShape);
+    }
+}
+
+/// Draws each agent's current target and steering force, replaced every frame.
+pub fn draw_agent_debug(
+    mut commands: Commands,
+    navmesh_debug: Res<NavmeshDebug>,
+    debug_assets: Res<NavmeshDebugAssets>,
+    shape_query: Query<Entity, With<AgentDebugShape>>,
+    agent_query: Query<(&Transform, &PathfindingAgent, &ExternalForce)>,
+) {
+    for entity in shape_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !navmesh_debug.enabled {
+        return;
+    }
+
+    for (transform, agent, force) in agent_query.iter() {
+        let position = transform.translation.truncate();
+
+        if let Some(target) = agent.current_target() {
+            commands
+                .spawn_bundle(debug_assets.line(position, target, debug_assets.target.clone()))
+                .insert(AgentDebugShape);
+            commands
+                .spawn_bundle(debug_assets.point(target, debug_assets.target.clone()))
+                .insert(AgentDebugShape);
+        }
+
+        if force.force != Vec2::ZERO {
+            commands
+                .spawn_bundle(debug_assets.line(
+                    position,
+                    position + force.force * FORCE_DRAW_SCALE,
+                    debug_assets.force.clone(),
+                ))
+                .insert(AgentDebugShape);
+        }
+    }
+}
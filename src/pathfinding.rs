@@ -1,66 +1,245 @@
+use std::time::{Duration, Instant};
+
 use bevy::prelude::*;
-use bevy_rapier2d::prelude::ExternalForce;
+use bevy_rapier2d::prelude::{ExternalForce, QueryFilter, RapierContext};
+
+use crate::{
+    flow_field::FlowField,
+    game_state::unless_frozen,
+    level::{Base, CurrentLevel},
+    level_asset::{LevelAsset, PathfindingMode},
+    navmesh::{EdgeKind, Navmesh, Waypoint},
+    navmesh_debug::{draw_agent_debug, sync_navmesh_debug, NavmeshDebug, NavmeshDebugAssets},
+    polishing_constants::ROUTE_VARIANCE,
+};
 
 pub struct PathfindingPlugin;
 
+/// Distance at which a waypoint counts as reached.
+const WAYPOINT_REACHED_DIST: f32 = 50.0;
+/// Agents further than this from the segment they are following get a new path.
+const OFF_PATH_DIST: f32 = 150.0;
+/// Agents that got no closer to their waypoint for this long get a new path.
+const STUCK_SECS: f32 = 2.0;
+/// How long an agent without a route waits before looking for one again.
+const NO_ROUTE_RETRY_SECS: f32 = 1.0;
+/// How much closer to its waypoint an agent has to get to count as progress.
+const PROGRESS_EPSILON: f32 = 5.0;
+/// How often the time spent on steering agents gets logged.
+const TIMINGS_REPORT_SECS: f32 = 5.0;
+
+/// Time spent steering agents in one pathfinding mode.
+#[derive(Default)]
+struct ModeTimings {
+    elapsed: Duration,
+    /// Agents steered summed over all measured frames.
+    agent_frames: u64,
+    max_agents: usize,
+}
+
+impl ModeTimings {
+    fn describe(&self) -> String {
+        if self.agent_frames == 0 {
+            return "not measured".to_string();
+        }
+        format!(
+            "{:.2}us per agent and frame, up to {} agents",
+            self.elapsed.as_secs_f64() * 1_000_000.0 / self.agent_frames as f64,
+            self.max_agents
+        )
+    }
+}
+
+/// Time spent steering agents in each mode, logged side by side so the modes
+/// can be compared. F4 switches the mode to measure the other one on the same wave.
+struct PathfindingTimings {
+    navmesh: ModeTimings,
+    flow_field: ModeTimings,
+    report_timer: Timer,
+}
+
+impl Default for PathfindingTimings {
+    fn default() -> Self {
+        PathfindingTimings {
+            navmesh: ModeTimings::default(),
+            flow_field: ModeTimings::default(),
+            report_timer: Timer::from_seconds(TIMINGS_REPORT_SECS, true),
+        }
+    }
+}
+
+impl PathfindingTimings {
+    fn record(&mut self, elapsed: Duration, agents: usize, delta: Duration, mode: PathfindingMode) {
+        if agents == 0 {
+            return;
+        }
+
+        let timings = match mode {
+            PathfindingMode::Navmesh => &mut self.navmesh,
+            PathfindingMode::FlowField => &mut self.flow_field,
+        };
+        timings.elapsed += elapsed;
+        timings.agent_frames += agents as u64;
+        timings.max_agents = timings.max_agents.max(agents);
+
+        if self.report_timer.tick(delta).just_finished() {
+            info!(
+                "pathfinding using {:?}: navmesh {}, flow field {}",
+                mode,
+                self.navmesh.describe(),
+                self.flow_field.describe()
+            );
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct PathfindingAgent {
     move_strength: f32,
+    speed_factor: f32,
+    can_dig: bool,
     current_idx: usize,
-}
-
-#[derive(Default)]
-struct Navmesh {
-    nodes: Vec<Vec2>,
+    path: Vec<Waypoint>,
+    route_seed: u32,
+    best_dist: f32,
+    stuck_secs: f32,
+    /// Time left until an agent that found no route looks for one again.
+    no_route_secs: f32,
+    target: Option<Vec2>,
 }
 
 impl PathfindingAgent {
-    pub fn new(move_strength: f32) -> Self {
+    pub fn new(move_strength: f32, can_dig: bool) -> Self {
         PathfindingAgent {
             move_strength,
+            speed_factor: 1.0,
+            can_dig,
             current_idx: 0,
+            path: Vec::new(),
+            route_seed: rand::random(),
+            best_dist: f32::INFINITY,
+            stuck_secs: 0.0,
+            no_route_secs: 0.0,
+            target: None,
         }
     }
-}
 
-impl Navmesh {
-    fn generate(preset_nodes: Vec<Vec2>, density_npu: f32) -> Self {
-        let mut nodes = vec![preset_nodes[0]];
-
-        for i in 1..preset_nodes.len() {
-            let last_node = preset_nodes[i - 1];
-            let next_node = preset_nodes[i];
+    /// The point the agent steered towards this frame.
+    pub fn current_target(&self) -> Option<Vec2> {
+        self.target
+    }
 
-            let dist = last_node.distance(next_node);
+    /// Scales the move strength, e.g. for enemies that are faster in some places.
+    pub fn set_speed_factor(&mut self, speed_factor: f32) {
+        self.speed_factor = speed_factor;
+    }
 
-            let steps = (dist * density_npu) as usize;
+    /// Whether the agent steers by the shared flow field instead of its own path.
+    pub fn follows_flow_field(&self, mode: PathfindingMode) -> bool {
+        // diggers keep their own path, the shared field does not dig
+        mode == PathfindingMode::FlowField && !self.can_dig
+    }
 
-            for step_nr in 0..steps {
-                let t = step_nr as f32 / steps as f32;
-                let new_node = last_node.lerp(next_node, t);
-                nodes.push(new_node);
+    /// Length of the way left to the base from `position`, infinite while the agent has no way.
+    pub fn remaining_path_length(
+        &self,
+        position: Vec2,
+        flow_field: &FlowField,
+        mode: PathfindingMode,
+    ) -> f32 {
+        if self.follows_flow_field(mode) {
+            if let Some(cost) = flow_field.cost_at(position) {
+                return cost;
             }
-            nodes.push(next_node);
         }
 
-        Navmesh { nodes }
+        let remaining = self.path.get(self.current_idx..).unwrap_or_default();
+        let next = match remaining.first() {
+            Some(next) => next,
+            None => return f32::INFINITY,
+        };
+
+        position.distance(next.position)
+            + remaining
+                .windows(2)
+                .map(|pair| pair[0].position.distance(pair[1].position))
+                .sum::<f32>()
+    }
+
+    /// Whether the agent is currently burrowing along a dig edge.
+    pub fn is_digging(&self) -> bool {
+        self.path
+            .get(self.current_idx)
+            .is_some_and(|waypoint| waypoint.kind == EdgeKind::Dig)
+    }
+
+    fn move_strength(&self) -> f32 {
+        self.move_strength * self.speed_factor
+    }
+
+    fn advance(&mut self) {
+        self.current_idx += 1;
+        self.best_dist = f32::INFINITY;
+        self.stuck_secs = 0.0;
+    }
+
+    fn replan(&mut self) {
+        self.path.clear();
+        self.current_idx = 0;
+        self.no_route_secs = 0.0;
+        self.best_dist = f32::INFINITY;
+        self.stuck_secs = 0.0;
+    }
+
+    /// Whether the agent got pushed away from the segment it is following,
+    /// or has not made progress towards its waypoint for a while.
+    fn is_lost(&mut self, position: Vec2, delta_secs: f32) -> bool {
+        if self.current_idx + 1 >= self.path.len() {
+            return false;
+        }
+
+        let target = self.path[self.current_idx].position;
+        let dist = target.distance(position);
+        if dist < self.best_dist - PROGRESS_EPSILON {
+            self.best_dist = dist;
+            self.stuck_secs = 0.0;
+        } else {
+            self.stuck_secs += delta_secs;
+        }
+
+        // the way to the first waypoint is a straight line from wherever the agent was
+        let off_path = self.current_idx > 0
+            && distance_to_segment(position, self.path[self.current_idx - 1].position, target)
+                > OFF_PATH_DIST;
+
+        off_path || self.stuck_secs > STUCK_SECS
+    }
+
+    /// Extra cost this agent sees on the edge from `a` to `b`, in `0..ROUTE_VARIANCE`.
+    /// Stable per agent, so it sticks to one of several similarly long routes.
+    fn route_noise(&self, a: usize, b: usize) -> f32 {
+        let mut hash = self.route_seed
+            ^ (a as u32).wrapping_mul(0x9e37_79b1)
+            ^ (b as u32).wrapping_mul(0x85eb_ca77);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x2c1b_3c6d);
+        hash ^= hash >> 12;
+
+        hash as f32 / u32::MAX as f32 * ROUTE_VARIANCE
     }
 }
 
-fn init_nav_mesh_debug(
-    mut commands: Commands,
-    navmesh: Res<Navmesh>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    for node in navmesh.nodes.iter() {
-        commands.spawn_bundle(ColorMesh2dBundle {
-            mesh: meshes.add(shape::Circle::new(5.0).into()).into(),
-            material: materials.add(ColorMaterial::from(Color::PURPLE)),
-            transform: Transform::from_translation(Vec3::new(node.x, node.y, 1.9)),
-            ..default()
-        });
+/// F4 switches between the pathfinding modes, to compare their timings on the same wave.
+fn toggle_pathfinding_mode(keys: Res<Input<KeyCode>>, mut mode: ResMut<PathfindingMode>) {
+    if !keys.just_pressed(KeyCode::F4) {
+        return;
     }
+
+    *mode = match *mode {
+        PathfindingMode::Navmesh => PathfindingMode::FlowField,
+        PathfindingMode::FlowField => PathfindingMode::Navmesh,
+    };
+    info!("pathfinding switched to {:?}", *mode);
 }
 
 fn update_pathfinding_agent(
@@ -69,56 +248,154 @@ fn update_pathfinding_agent(
         With<PathfindingAgent>,
     >,
     navmesh: Res<Navmesh>,
+    flow_field: Res<FlowField>,
+    mode: Res<PathfindingMode>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+    mut timings: ResMut<PathfindingTimings>,
 ) {
+    let started = Instant::now();
+
     for (agent_transform, mut agent, mut agent_move_force) in agent_query.iter_mut() {
-        agent_move_force.force =
-            get_force_from_navmesh(agent_transform.translation, &navmesh, &mut agent);
+        let position = agent_transform.translation.truncate();
+
+        if agent.follows_flow_field(*mode) {
+            if let Some(target) = flow_field.sample(position) {
+                agent.target = Some(target);
+                agent_move_force.force =
+                    (target - position).normalize_or_zero() * agent.move_strength();
+                continue;
+            }
+        }
+
+        // agents too far from the flow field fall back to their own path
+        if navmesh.is_changed() || agent.is_lost(position, time.delta_seconds()) {
+            agent.replan();
+        }
+
+        // only head for nodes without level geometry in between
+        let is_reachable = |node: Vec2| {
+            rapier_context
+                .cast_ray(
+                    position,
+                    node - position,
+                    1.0,
+                    true,
+                    QueryFilter::only_fixed(),
+                )
+                .is_none()
+        };
+
+        agent_move_force.force = get_force_from_navmesh(
+            agent_transform.translation,
+            &navmesh,
+            &mut agent,
+            is_reachable,
+            time.delta_seconds(),
+        );
     }
+
+    timings.record(
+        started.elapsed(),
+        agent_query.iter().len(),
+        time.delta(),
+        *mode,
+    );
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0.0 {
+        return point.distance(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance(start + segment * t)
 }
 
 fn get_force_from_navmesh(
     sample_position: Vec3,
     navmesh: &Navmesh,
     agent: &mut PathfindingAgent,
+    is_reachable: impl Fn(Vec2) -> bool,
+    delta_secs: f32,
 ) -> Vec2 {
     let sample_position = sample_position.truncate();
 
+    agent.no_route_secs = (agent.no_route_secs - delta_secs).max(0.0);
+    if agent.path.is_empty() && agent.no_route_secs <= 0.0 {
+        match navmesh.find_path(sample_position, is_reachable, agent.can_dig, |a, b| {
+            agent.route_noise(a, b)
+        }) {
+            Some(path) => agent.path = path,
+            None => agent.no_route_secs = NO_ROUTE_RETRY_SECS,
+        }
+        agent.current_idx = 0;
+    }
+
     loop {
-        if agent.current_idx >= (navmesh.nodes.len() - 1) {
+        if agent.current_idx + 1 >= agent.path.len() {
+            agent.target = None;
             return Vec2::ZERO;
-         }
-        let current_node = navmesh.nodes[agent.current_idx];
+        }
+        let current_node = agent.path[agent.current_idx].position;
         let dir = current_node - sample_position;
         let dist = current_node.distance(sample_position);
 
-        if dist < 50.0 {
-            agent.current_idx = agent.current_idx + 1;
-            
+        if dist < WAYPOINT_REACHED_DIST {
+            agent.advance();
+
             continue;
         }
-        return dir.normalize() * agent.move_strength;
+        agent.target = Some(current_node);
+        return dir.normalize() * agent.move_strength();
+    }
+}
+
+/// Regenerates the navmesh and flow field whenever a level gets spawned, so enemies follow its geometry.
+fn rebuild_navmesh_on_level_spawn(
+    mut commands: Commands,
+    spawned_base_query: Query<(), Added<Base>>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+) {
+    if spawned_base_query.is_empty() {
+        return;
+    }
+
+    if let Some(level) = levels.get(&current_level.0) {
+        let navmesh = Navmesh::from_level(level);
+        for spawn_point in level.spawn_points.iter() {
+            if navmesh
+                .find_path(spawn_point.position, |_| true, false, |_, _| 0.0)
+                .is_none()
+            {
+                warn!("spawn point {} has no route to the base", spawn_point.name);
+            }
+        }
+        // built for every level, so the modes can be compared anywhere
+        let flow_field = FlowField::from_navmesh(&navmesh);
+
+        commands.insert_resource(navmesh);
+        commands.insert_resource(flow_field);
+        commands.insert_resource(level.pathfinding);
     }
 }
 
 impl Plugin for PathfindingPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Navmesh::generate(
-            vec![
-                Vec2::new(180.0, 135.0), 
-                Vec2::new(1160.0, 135.0),
-                Vec2::new(1160.0, 460.0),
-                Vec2::new(640.0, 800.0),
-                Vec2::new(100.0, 800.0),
-                Vec2::new(100.0, 1150.0),
-                Vec2::new(330.0, 1400.0),
-                Vec2::new(1110.0, 1400.0),
-                Vec2::new(1110.0, 1800.0),
-                Vec2::new(1500.0, 1800.0),
-            ],
-            0.1,
-        ));
-        // app.add_startup_system(init_nav_mesh_debug);
-        app.add_system(update_pathfinding_agent);
+        app.init_resource::<Navmesh>()
+            .init_resource::<FlowField>()
+            .init_resource::<PathfindingMode>()
+            .init_resource::<PathfindingTimings>();
+        app.add_system(rebuild_navmesh_on_level_spawn);
+        app.init_resource::<NavmeshDebug>()
+            .init_resource::<NavmeshDebugAssets>()
+            .add_system(sync_navmesh_debug)
+            .add_system(draw_agent_debug.after(update_pathfinding_agent));
+        app.add_system(toggle_pathfinding_mode)
+            // while physics is off nobody moves, so agents would all count as stuck
+            .add_system(update_pathfinding_agent.with_run_criteria(unless_frozen));
     }
 }
-
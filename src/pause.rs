@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    game_state::{reset_run, AppState, RunStats, Wallet, WaveControler},
+    level::LevelComponent,
+    menu::{border, button, button_text, menu_background, overlay},
+};
+
+pub struct PausePlugin;
+
+#[derive(Component)]
+struct PauseUi;
+
+#[derive(Component, Debug)]
+enum PauseButton {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+/// Buttons of the pause menu whose interaction changed this frame.
+type PauseButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static PauseButton),
+    (Changed<Interaction>, With<Button>),
+>;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Paused)
+                .with_system(pause_simulation)
+                .with_system(spawn_pause_menu),
+        )
+        .add_system_set(SystemSet::on_update(AppState::Paused).with_system(pause_button_system))
+        .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(resume_simulation));
+    }
+}
+
+fn pause_simulation(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn resume_simulation(
+    mut commands: Commands,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    ui_query: Query<Entity, With<PauseUi>>,
+) {
+    for entity in ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    rapier_config.physics_pipeline_active = true;
+}
+
+fn spawn_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(overlay())
+        .insert(PauseUi)
+        .with_children(|parent| {
+            parent.spawn_bundle(border()).with_children(|parent| {
+                parent
+                    .spawn_bundle(menu_background())
+                    .with_children(|parent| {
+                        for (label, pause_button) in [
+                            ("Resume", PauseButton::Resume),
+                            ("Restart level", PauseButton::Restart),
+                            ("Quit to menu", PauseButton::QuitToMenu),
+                        ] {
+                            parent
+                                .spawn_bundle(button())
+                                .with_children(|parent| {
+                                    parent.spawn_bundle(button_text(&asset_server, label));
+                                })
+                                .insert(pause_button);
+                        }
+                    });
+            });
+        });
+}
+
+fn pause_button_system(
+    mut commands: Commands,
+    buttons: PauseButtonQuery,
+    mut state: ResMut<State<AppState>>,
+    mut wave_controler: ResMut<WaveControler>,
+    mut run_stats: ResMut<RunStats>,
+    mut wallet: ResMut<Wallet>,
+    level_query: Query<Entity, With<LevelComponent>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        let result = match button {
+            PauseButton::Resume => state.pop(),
+            // replacing the whole stack leaves the paused phase through its regular
+            // exit, the level respawns when the build phase starts again
+            PauseButton::Restart => {
+                reset_run(
+                    &mut commands,
+                    level_query.iter(),
+                    &mut wave_controler,
+                    &mut run_stats,
+                    &mut wallet,
+                );
+                state.replace(AppState::Build)
+            }
+            PauseButton::QuitToMenu => {
+                reset_run(
+                    &mut commands,
+                    level_query.iter(),
+                    &mut wave_controler,
+                    &mut run_stats,
+                    &mut wallet,
+                );
+                state.replace(AppState::MainMenu)
+            }
+        };
+        if let Err(err) = result {
+            warn!("couldn't leave the pause menu: {:?}", err);
+        }
+    }
+}
@@ -2,6 +2,22 @@ pub const ENEMY_STRENGTH: f32 = 550.0;
 pub const GADGET_MIN_DISTANCE: f32 = 1000.0;
 pub const WATER_STRENGTH: f32 = 40.0;
 pub const WATER_SIZE: f32 = 30.0;
-pub const SHOOTING_CHANCE: f32 = 0.9;
+pub const CANNON_SHOTS_PER_SECOND: f32 = 6.0;
 pub const PIXELS_PER_METER_POLISHING: f32 = 100.0;
 pub const ATTACK_COUNTDOWN: f64 = 35.0;
+pub const ROUTE_VARIANCE: f32 = 0.5;
+pub const ENEMY_HEALTH: f32 = 100.0;
+pub const WATER_DAMAGE: f32 = 4.0;
+pub const BASE_HEALTH: f32 = 100.0;
+pub const LAVA_STRENGTH: f32 = 45.0;
+pub const LAVA_SHOTS_PER_SECOND: f32 = 2.0;
+pub const LAVA_DAMAGE: f32 = 2.0;
+pub const LAVA_BURN_DPS: f32 = 8.0;
+pub const LAVA_BURN_SECS: f32 = 3.0;
+pub const STARTING_GOLD: u32 = 150;
+pub const WAVE_BONUS_GOLD: u32 = 50;
+pub const SELL_REFUND_FRACTION: f32 = 0.6;
+pub const PROJECTILE_SPEED_PER_STRENGTH: f32 = 14.0;
+pub const AIM_MAX_FLIGHT_SECS: f32 = 3.0;
+pub const MAX_LIVE_DROPLETS: usize = 300;
+pub const DROPLET_LIFETIME_SECS: f32 = 4.0;
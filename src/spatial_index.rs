@@ -0,0 +1,128 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::enemy::Enemy;
+
+/// Side length in pixels of a grid cell, about the reach of the shortest proximity checks.
+const CELL_SIZE: f32 = 256.0;
+
+pub struct SpatialIndexPlugin;
+
+/// Uniform grid of enemy positions, rebuilt at the start of every frame.
+///
+/// Enemies despawned during the frame stay in the index until the next rebuild,
+/// so look the entities up in a query instead of assuming they still exist.
+#[derive(Debug)]
+pub struct SpatialIndex {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    /// Bounds of the occupied cells, inverted while the index is empty.
+    min_cell: IVec2,
+    max_cell: IVec2,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        SpatialIndex {
+            cells: HashMap::default(),
+            min_cell: IVec2::splat(i32::MAX),
+            max_cell: IVec2::splat(i32::MIN),
+        }
+    }
+}
+
+impl Plugin for SpatialIndexPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_spatial_index);
+    }
+}
+
+impl SpatialIndex {
+    fn cell_of(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn insert(&mut self, entity: Entity, position: Vec2) {
+        let cell = Self::cell_of(position);
+        self.min_cell = self.min_cell.min(cell);
+        self.max_cell = self.max_cell.max(cell);
+        self.cells.entry(cell).or_default().push((entity, position));
+    }
+
+    fn clear(&mut self) {
+        *self = SpatialIndex::default();
+    }
+
+    /// Enemies whose center is at most `radius` away from `point`.
+    pub fn within_radius(
+        &self,
+        point: Vec2,
+        radius: f32,
+    ) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let from = Self::cell_of(point - radius).max(self.min_cell);
+        let to = Self::cell_of(point + radius).min(self.max_cell);
+
+        (from.x..=to.x)
+            .flat_map(move |x| (from.y..=to.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, position)| position.distance(point) <= radius)
+    }
+
+    /// The enemy closest to `point`, if there is one within `max_radius`.
+    pub fn nearest(&self, point: Vec2, max_radius: f32) -> Option<(Entity, Vec2)> {
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        let center = Self::cell_of(point);
+        // rings past the occupied cells can't hold anything
+        let last_ring = (center - self.min_cell)
+            .abs()
+            .max((self.max_cell - center).abs())
+            .max_element()
+            .min((max_radius / CELL_SIZE).ceil() as i32);
+
+        let mut nearest: Option<(f32, Entity, Vec2)> = None;
+        for ring in 0..=last_ring {
+            // everything from this ring on is at least `ring - 1` cells away
+            if let Some((distance, _, _)) = nearest {
+                if distance < (ring - 1) as f32 * CELL_SIZE {
+                    break;
+                }
+            }
+
+            for x in -ring..=ring {
+                for y in -ring..=ring {
+                    if x.abs() != ring && y.abs() != ring {
+                        continue;
+                    }
+                    let entries = match self.cells.get(&(center + IVec2::new(x, y))) {
+                        Some(entries) => entries,
+                        None => continue,
+                    };
+                    for (entity, position) in entries.iter() {
+                        let distance = position.distance(point);
+                        if distance <= max_radius
+                            && nearest.is_none_or(|(best, _, _)| distance < best)
+                        {
+                            nearest = Some((distance, *entity, *position));
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest.map(|(_, entity, position)| (entity, position))
+    }
+}
+
+fn update_spatial_index(
+    mut spatial_index: ResMut<SpatialIndex>,
+    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+) {
+    spatial_index.clear();
+    for (entity, transform) in enemy_query.iter() {
+        spatial_index.insert(entity, transform.translation.truncate());
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+/// Which enemy in range a gadget shoots at, cycled with T in the build phase.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TargetingMode {
+    #[default]
+    Closest,
+    /// The enemy with the least way left to the base.
+    First,
+    LowestHealth,
+    Strongest,
+    LastSpawned,
+}
+
+/// What a gadget knows about an enemy in range when picking its target.
+pub struct TargetCandidate {
+    pub distance: f32,
+    pub remaining_path: f32,
+    pub health: f32,
+    pub spawned_at: f64,
+}
+
+impl TargetingMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetingMode::Closest => "closest",
+            TargetingMode::First => "first",
+            TargetingMode::LowestHealth => "lowest health",
+            TargetingMode::Strongest => "strongest",
+            TargetingMode::LastSpawned => "last spawned",
+        }
+    }
+
+    pub fn next(&self) -> TargetingMode {
+        match self {
+            TargetingMode::Closest => TargetingMode::First,
+            TargetingMode::First => TargetingMode::LowestHealth,
+            TargetingMode::LowestHealth => TargetingMode::Strongest,
+            TargetingMode::Strongest => TargetingMode::LastSpawned,
+            TargetingMode::LastSpawned => TargetingMode::Closest,
+        }
+    }
+
+    /// Ranks a candidate for this mode, the lowest rank gets shot.
+    pub fn rank(&self, candidate: &TargetCandidate) -> f32 {
+        match self {
+            TargetingMode::Closest => candidate.distance,
+            TargetingMode::First => candidate.remaining_path,
+            TargetingMode::LowestHealth => candidate.health,
+            TargetingMode::Strongest => -candidate.health,
+            TargetingMode::LastSpawned => -candidate.spawned_at as f32,
+        }
+    }
+}
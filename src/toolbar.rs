@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{
+    gadget_catalog::{GadgetKind, SelectedGadget, GADGET_KINDS},
+    game_state::{AppState, Wallet},
+};
+
+const BUTTON_COLOR: &str = "45A7BA";
+const SELECTED_BUTTON_COLOR: &str = "E2C044";
+const UNAFFORDABLE_BUTTON_COLOR: &str = "27373B";
+const HOTKEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+pub struct ToolbarPlugin;
+
+/// Build phase bar along the bottom of the screen to pick the next gadget.
+#[derive(Component)]
+struct Toolbar;
+
+#[derive(Component)]
+struct ToolbarButton(GadgetKind);
+
+impl Plugin for ToolbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedGadget>()
+            .add_system_set(SystemSet::on_enter(AppState::Build).with_system(spawn_toolbar))
+            .add_system_set(
+                SystemSet::on_update(AppState::Build)
+                    .with_system(toolbar_button_system)
+                    .with_system(select_gadget_hotkeys)
+                    .with_system(
+                        highlight_selected_gadget
+                            .after(toolbar_button_system)
+                            .after(select_gadget_hotkeys),
+                    ),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Build).with_system(despawn_toolbar));
+    }
+}
+
+fn spawn_toolbar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Oswald-SemiBold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: UiColor(Color::NONE),
+            ..default()
+        })
+        .insert(Toolbar)
+        .with_children(|parent| {
+            for (idx, kind) in GADGET_KINDS.iter().enumerate() {
+                let definition = kind.definition();
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(260.0), Val::Px(80.0)),
+                            align_items: AlignItems::Center,
+                            margin: UiRect::all(Val::Px(5.0)),
+                            padding: UiRect::all(Val::Px(5.0)),
+                            ..default()
+                        },
+                        color: UiColor(Color::hex(BUTTON_COLOR).unwrap()),
+                        ..default()
+                    })
+                    .insert(ToolbarButton(*kind))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(ImageBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(50.0), Val::Px(68.0)),
+                                margin: UiRect::all(Val::Px(5.0)),
+                                ..default()
+                            },
+                            image: UiImage(asset_server.load(definition.sprite)),
+                            ..default()
+                        });
+                        parent.spawn_bundle(TextBundle::from_section(
+                            format!("{} {}\n{} gold", idx + 1, definition.name, definition.cost),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 26.0,
+                                color: Color::hex("0C1E21").unwrap(),
+                            },
+                        ));
+                    });
+            }
+
+            parent.spawn_bundle(
+                TextBundle::from_section(
+                    "U upgrade gadget\nT targeting mode\nright click sell",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 22.0,
+                        color: Color::hex(BUTTON_COLOR).unwrap(),
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                }),
+            );
+        });
+}
+
+fn despawn_toolbar(mut commands: Commands, toolbar_query: Query<Entity, With<Toolbar>>) {
+    for entity in toolbar_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn toolbar_button_system(
+    buttons: Query<(&Interaction, &ToolbarButton), Changed<Interaction>>,
+    mut selected_gadget: ResMut<SelectedGadget>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            selected_gadget.0 = button.0;
+        }
+    }
+}
+
+fn select_gadget_hotkeys(keys: Res<Input<KeyCode>>, mut selected_gadget: ResMut<SelectedGadget>) {
+    for (idx, key) in HOTKEYS.iter().enumerate() {
+        if keys.just_pressed(*key) {
+            if let Some(kind) = GadgetKind::from_hotkey(idx + 1) {
+                selected_gadget.0 = kind;
+            }
+        }
+    }
+}
+
+fn highlight_selected_gadget(
+    selected_gadget: Res<SelectedGadget>,
+    wallet: Res<Wallet>,
+    mut buttons: Query<(&ToolbarButton, &mut UiColor)>,
+) {
+    for (button, mut color) in buttons.iter_mut() {
+        let hex = if button.0 == selected_gadget.0 {
+            SELECTED_BUTTON_COLOR
+        } else if button.0.definition().cost > wallet.balance {
+            UNAFFORDABLE_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        *color = UiColor(Color::hex(hex).unwrap());
+    }
+}
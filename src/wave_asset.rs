@@ -0,0 +1,100 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, Error, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use crate::enemy::EnemyType;
+
+/// Seconds between two spawns of a group if the wave file does not say otherwise.
+const DEFAULT_SPAWN_INTERVAL: f32 = 2.0;
+
+/// The waves of a level as authored in `assets/levels/*.waves.ron`.
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "b1e7d3a2-6c4f-4e8b-a5d9-2f0c8e1b7a63"]
+pub struct WaveScript {
+    pub waves: Vec<WaveDescription>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WaveDescription {
+    pub groups: Vec<WaveGroup>,
+}
+
+/// `count` enemies of one type, spawned every `interval` seconds after waiting
+/// `delay` seconds from the start of the wave.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveGroup {
+    pub enemy: EnemyType,
+    pub count: u32,
+    #[serde(default = "default_spawn_interval")]
+    pub interval: f32,
+    #[serde(default)]
+    pub delay: f32,
+    /// Name of the spawn point to use, the level's default one if left out.
+    #[serde(default)]
+    pub spawn_point: Option<String>,
+}
+
+fn default_spawn_interval() -> f32 {
+    DEFAULT_SPAWN_INTERVAL
+}
+
+impl WaveScript {
+    /// Wave number `wave`, counting from 1. Once the script runs out the last wave
+    /// repeats, with one more enemy per group every time.
+    pub fn wave(&self, wave: u32) -> Option<WaveDescription> {
+        let last = self.waves.len() as u32;
+        let wave = wave.max(1);
+        if wave <= last {
+            return self.waves.get(wave as usize - 1).cloned();
+        }
+
+        let mut description = self.waves.last()?.clone();
+        for group in description.groups.iter_mut() {
+            group.count += wave - last;
+        }
+        Some(description)
+    }
+}
+
+impl WaveDescription {
+    /// Fallback for levels without a wave file: `wave` enemies of every type
+    /// unlocked so far, one type after the other.
+    pub fn generated(wave: u32) -> Self {
+        let groups = EnemyType::for_wave(wave)
+            .into_iter()
+            .enumerate()
+            .map(|(i, enemy)| WaveGroup {
+                enemy,
+                count: wave.max(1),
+                interval: DEFAULT_SPAWN_INTERVAL,
+                delay: i as f32 * DEFAULT_SPAWN_INTERVAL * 0.5,
+                spawn_point: None,
+            })
+            .collect();
+
+        WaveDescription { groups }
+    }
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), Error>> {
+        Box::pin(async move {
+            let waves = ron::de::from_bytes::<WaveScript>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(waves));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}